@@ -0,0 +1,112 @@
+use std::hint::black_box;
+
+use cgmath::{Deg, InnerSpace, Rad, Vector3};
+use criterion::{criterion_group, criterion_main, Criterion};
+use curve_ray::raytracing::{
+    camera::Camera,
+    curve_triangle::CurveTriangle,
+    ray::Ray,
+    root_solver::{Convergence, RootIsolation, RootMethod, RootSolver},
+};
+use curve_ray::shapes::get_curve_sphere;
+
+const METHODS: [(&str, RootMethod); 3] = [
+    ("bisection", RootMethod::Bisection),
+    ("secant", RootMethod::Secant),
+    ("newton", RootMethod::Newton),
+];
+
+fn camera_rays(resolution: u32) -> Vec<Ray> {
+    let camera = Camera {
+        origin: Vector3::new(0., 0., -2.),
+        direction: Vector3::new(0., 0., 1.),
+        fov: Rad::from(Deg(90.)).0,
+        ratio: 1.,
+    };
+    let mut rays = Vec::new();
+    for x in 0..resolution {
+        for y in 0..resolution {
+            let view_x = (2 * x) as f32 / resolution as f32 - 1.;
+            let view_y = 1. - (2 * y) as f32 / resolution as f32;
+            rays.push(camera.get_ray_in_viewport(view_x, view_y));
+        }
+    }
+    rays
+}
+
+/// Legacy bisection, every refinement method and the root isolation
+fn solvers() -> Vec<(&'static str, RootSolver)> {
+    let mut solvers = vec![("legacy", RootSolver::legacy())];
+    for (name, method) in METHODS {
+        solvers.push((name, RootSolver::default().with_method(method)));
+    }
+    solvers.push((
+        "isolated",
+        RootSolver::default().with_isolation(RootIsolation::default()),
+    ));
+    solvers
+}
+
+fn sphere_with_solver(solver: RootSolver) -> Vec<CurveTriangle> {
+    let mut sphere = get_curve_sphere();
+    for part in sphere.iter_mut() {
+        part.solver = solver;
+    }
+    sphere
+}
+
+/// Distance to the unit sphere is known, so the error of every solver can be measured exactly
+fn report_accuracy(rays: &[Ray]) {
+    for (name, solver) in solvers() {
+        let sphere = sphere_with_solver(solver);
+        let (mut hits, mut not_converged, mut max_error, mut error_sum, mut iterations) =
+            (0, 0, 0f32, 0f32, 0);
+        for ray in rays {
+            for part in sphere.iter() {
+                // only roots accepted as hits, like renderers see them
+                if let (Ok(root), Ok(hit)) = (part.solve(ray), part.try_intersect(ray)) {
+                    let radius_error = (hit.point.magnitude() - 1.).abs();
+                    hits += 1;
+                    iterations += root.iterations;
+                    if root.convergence != Convergence::Converged {
+                        not_converged += 1;
+                    }
+                    max_error = max_error.max(radius_error);
+                    error_sum += radius_error;
+                }
+            }
+        }
+        println!(
+            "{:>9}: hits {} not converged {} mean iterations {:.2} max error {:.2e} mean error {:.2e}",
+            name,
+            hits,
+            not_converged,
+            iterations as f32 / hits.max(1) as f32,
+            max_error,
+            error_sum / hits.max(1) as f32,
+        );
+    }
+}
+
+fn bench_curve_intersect(c: &mut Criterion) {
+    let rays = camera_rays(64);
+    report_accuracy(&rays);
+
+    let mut group = c.benchmark_group("curve_intersect");
+    for (name, solver) in solvers() {
+        let sphere = sphere_with_solver(solver);
+        group.bench_function(name, |b| {
+            b.iter(|| {
+                for ray in rays.iter() {
+                    for part in sphere.iter() {
+                        let _ = black_box(part.solve(black_box(ray)));
+                    }
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_curve_intersect);
+criterion_main!(benches);
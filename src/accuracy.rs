@@ -0,0 +1,355 @@
+use std::collections::HashMap;
+
+use cgmath::{Deg, InnerSpace, Rad, Vector3};
+use serde::Serialize;
+
+use crate::raytracing::{
+    camera::Camera,
+    curve_triangle::CurveTriangle,
+    fit::{Sphere, TargetSurface},
+    hit::Intersect,
+    mesh::Mesh,
+    ray::Ray,
+};
+
+/// Rays closer to the silhouette of the reference than this part of the radius
+/// are neither holes nor false hits
+const SILHOUETTE_MARGIN: f32 = 1e-3;
+
+/// Camera origins around the reference, the cameras look at its center
+const VIEWPOINTS: [[f32; 3]; 3] = [[0., 0., -3.], [1.7, 1.3, -2.1], [-2.2, -1.1, 1.6]];
+
+/// Field of view of the cameras, covers the reference from the viewpoints
+const VIEW_FOV: f32 = 90.;
+
+#[derive(Debug, Clone, Copy)]
+pub struct AccuracyOptions {
+    /// Subdivisions of the base edges for the surface samples
+    pub surface_samples: usize,
+    /// Camera rays per side of every viewpoint
+    pub ray_resolution: u32,
+    /// Accuracy passed to `CurveTriangle::triangulate`
+    pub triangulation_accuracy: i32,
+    /// Chordal error passed to `Mesh::tessellate`
+    pub tessellation_tolerance: f32,
+    /// Subdivision limit passed to `Mesh::tessellate`
+    pub tessellation_max_depth: u32,
+}
+
+impl Default for AccuracyOptions {
+    fn default() -> Self {
+        AccuracyOptions {
+            surface_samples: 64,
+            ray_resolution: 128,
+            triangulation_accuracy: 5,
+            tessellation_tolerance: 1e-3,
+            tessellation_max_depth: 6,
+        }
+    }
+}
+
+/// Statistics of absolute errors
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct ErrorStats {
+    pub count: usize,
+    pub max: f64,
+    pub mean: f64,
+    pub rms: f64,
+}
+
+impl ErrorStats {
+    pub fn from_errors<I: IntoIterator<Item = f32>>(errors: I) -> ErrorStats {
+        let (mut count, mut max, mut sum, mut sum_sqr) = (0, 0f64, 0f64, 0f64);
+        for error in errors {
+            let error = (error as f64).abs();
+            count += 1;
+            max = max.max(error);
+            sum += error;
+            sum_sqr += error * error;
+        }
+        if count == 0 {
+            return ErrorStats::default();
+        }
+        ErrorStats {
+            count,
+            max,
+            mean: sum / count as f64,
+            rms: (sum_sqr / count as f64).sqrt(),
+        }
+    }
+}
+
+/// Surface points by barycentric coords of the patches
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SurfaceAccuracy {
+    pub radial_error: ErrorStats,
+    /// Angle between the surface and the reference normals, degrees
+    pub normal_deviation: ErrorStats,
+}
+
+/// Nearest hits of the camera rays
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct IntersectionAccuracy {
+    pub rays: usize,
+    pub hits: usize,
+    /// Rays through the reference without a hit
+    pub holes: usize,
+    /// Hits of rays passing by the reference
+    pub false_hits: usize,
+    pub radial_error: ErrorStats,
+    /// Angle between the hit and the reference normals, degrees
+    pub normal_deviation: ErrorStats,
+}
+
+/// Uniform triangulation of the patches
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TriangulationAccuracy {
+    pub accuracy: i32,
+    pub triangles: usize,
+    pub vertex_radial_error: ErrorStats,
+    /// Of the centers of the triangles, the chordal error
+    pub center_radial_error: ErrorStats,
+    /// Angle between the face and the reference normals in the centers, degrees
+    pub normal_deviation: ErrorStats,
+    /// Edges of the welded triangulation with a single face, cracks between or inside the patches
+    pub open_edges: usize,
+}
+
+/// Adaptive welded tessellation of the patches, see `Mesh::tessellate`
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct TessellationAccuracy {
+    pub tolerance: f32,
+    pub max_depth: u32,
+    pub triangles: usize,
+    pub vertex_radial_error: ErrorStats,
+    /// Of the centers of the triangles, the chordal error
+    pub center_radial_error: ErrorStats,
+    /// Angle between the face and the reference normals in the centers, degrees,
+    /// faces are wound outward so flipped faces are near 180
+    pub normal_deviation: ErrorStats,
+    /// Edges with a single face, cracks or T-junctions between the patches
+    pub open_edges: usize,
+}
+
+/// Deviation of the curve patches from the analytic sphere they approximate
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct AccuracyReport {
+    pub patches: usize,
+    /// `CurveTriangle::get_surface_point_by_bary`, built on `CurveTriangle::curve`
+    pub surface: SurfaceAccuracy,
+    /// `CurveTriangle::get_surface_point_by_bary_sqrt`, built on `CurveTriangle::curve_sqrt`
+    pub surface_sqrt: SurfaceAccuracy,
+    pub intersection: IntersectionAccuracy,
+    pub triangulation: TriangulationAccuracy,
+    pub tessellation: TessellationAccuracy,
+}
+
+impl AccuracyReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("report has only numbers")
+    }
+}
+
+/// Measure the patches against the sphere they should form, like the octants of `get_curve_sphere`.
+/// Triangulations of the patches are replaced.
+pub fn sphere_report(
+    patches: &mut [CurveTriangle],
+    sphere: &Sphere,
+    options: &AccuracyOptions,
+) -> AccuracyReport {
+    AccuracyReport {
+        patches: patches.len(),
+        surface: surface_accuracy(patches, sphere, options.surface_samples, |patch, bary| {
+            patch.get_surface_point_by_bary(bary)
+        }),
+        surface_sqrt: surface_accuracy(patches, sphere, options.surface_samples, |patch, bary| {
+            patch.get_surface_point_by_bary_sqrt(bary)
+        }),
+        intersection: intersection_accuracy(patches, sphere, options.ray_resolution),
+        triangulation: triangulation_accuracy(patches, sphere, options.triangulation_accuracy),
+        tessellation: tessellation_accuracy(
+            patches,
+            sphere,
+            options.tessellation_tolerance,
+            options.tessellation_max_depth,
+        ),
+    }
+}
+
+fn surface_accuracy<F>(
+    patches: &[CurveTriangle],
+    sphere: &Sphere,
+    samples: usize,
+    surface_point: F,
+) -> SurfaceAccuracy
+where
+    F: Fn(&CurveTriangle, Vector3<f32>) -> Vector3<f32>,
+{
+    let (mut radial_errors, mut normal_deviations) = (Vec::new(), Vec::new());
+    for patch in patches.iter() {
+        for i in 0..=samples {
+            for j in 0..=samples - i {
+                let (w1, w2) = (i as f32 / samples as f32, j as f32 / samples as f32);
+                let bary = Vector3::new((1. - w1 - w2).max(0.), w1, w2);
+                let point = surface_point(patch, bary);
+                radial_errors.push(sphere.distance(point));
+                normal_deviations.push(angle(patch.normal_at_bary(bary), sphere.normal(point)));
+            }
+        }
+    }
+    SurfaceAccuracy {
+        radial_error: ErrorStats::from_errors(radial_errors),
+        normal_deviation: ErrorStats::from_errors(normal_deviations),
+    }
+}
+
+fn intersection_accuracy(
+    patches: &[CurveTriangle],
+    sphere: &Sphere,
+    resolution: u32,
+) -> IntersectionAccuracy {
+    let mut report = IntersectionAccuracy {
+        rays: 0,
+        hits: 0,
+        holes: 0,
+        false_hits: 0,
+        radial_error: ErrorStats::default(),
+        normal_deviation: ErrorStats::default(),
+    };
+    let (mut radial_errors, mut normal_deviations) = (Vec::new(), Vec::new());
+
+    for viewpoint in VIEWPOINTS {
+        let origin = sphere.center + Vector3::from(viewpoint) * sphere.radius;
+        let camera = Camera {
+            origin,
+            direction: (sphere.center - origin).normalize(),
+            fov: Rad::from(Deg(VIEW_FOV)).0,
+            ratio: 1.,
+        };
+        for x in 0..resolution {
+            for y in 0..resolution {
+                let view_x = (2 * x + 1) as f32 / resolution as f32 - 1.;
+                let view_y = 1. - (2 * y + 1) as f32 / resolution as f32;
+                let ray = camera.get_ray_in_viewport(view_x, view_y);
+                report.rays += 1;
+
+                let hit = patches
+                    .iter()
+                    .filter_map(|patch| patch.intersect(&ray))
+                    .min_by(|a, b| a.t.total_cmp(&b.t));
+                let miss_distance = sphere_miss_distance(sphere, &ray);
+                match hit {
+                    Some(hit) => {
+                        report.hits += 1;
+                        if miss_distance > SILHOUETTE_MARGIN {
+                            report.false_hits += 1;
+                            continue;
+                        }
+                        radial_errors.push(sphere.distance(hit.point));
+                        normal_deviations.push(angle(hit.outward_normal(), sphere.normal(hit.point)));
+                    }
+                    None if miss_distance < -SILHOUETTE_MARGIN => report.holes += 1,
+                    None => {}
+                }
+            }
+        }
+    }
+    report.radial_error = ErrorStats::from_errors(radial_errors);
+    report.normal_deviation = ErrorStats::from_errors(normal_deviations);
+    report
+}
+
+fn triangulation_accuracy(
+    patches: &mut [CurveTriangle],
+    sphere: &Sphere,
+    accuracy: i32,
+) -> TriangulationAccuracy {
+    for patch in patches.iter_mut() {
+        patch.triangulate(accuracy);
+    }
+    let mesh = Mesh::from_triangulations(patches);
+
+    let vertex_radial_error =
+        ErrorStats::from_errors(mesh.vertexes.iter().map(|&vertex| sphere.distance(vertex)));
+    let (mut center_errors, mut normal_deviations) = (Vec::new(), Vec::new());
+    for triangle in mesh.triangles() {
+        let [a, b, c] = triangle.vertexes;
+        let center = (a + b + c) / 3.;
+        center_errors.push(sphere.distance(center));
+        // triangulation winding is not defined, compare the lines of the normals
+        let deviation = angle(triangle.normal(), sphere.normal(center));
+        normal_deviations.push(deviation.min(180. - deviation));
+    }
+
+    TriangulationAccuracy {
+        accuracy,
+        triangles: mesh.indices.len(),
+        vertex_radial_error,
+        center_radial_error: ErrorStats::from_errors(center_errors),
+        normal_deviation: ErrorStats::from_errors(normal_deviations),
+        open_edges: open_edges(&mesh),
+    }
+}
+
+fn tessellation_accuracy(
+    patches: &[CurveTriangle],
+    sphere: &Sphere,
+    tolerance: f32,
+    max_depth: u32,
+) -> TessellationAccuracy {
+    let mesh = Mesh::tessellate(patches, tolerance, max_depth);
+
+    let vertex_radial_error =
+        ErrorStats::from_errors(mesh.vertexes.iter().map(|&vertex| sphere.distance(vertex)));
+    let (mut center_errors, mut normal_deviations) = (Vec::new(), Vec::new());
+    for triangle in mesh.triangles() {
+        let [a, b, c] = triangle.vertexes;
+        let center = (a + b + c) / 3.;
+        center_errors.push(sphere.distance(center));
+        normal_deviations.push(angle(triangle.normal(), sphere.normal(center)));
+    }
+
+    TessellationAccuracy {
+        tolerance,
+        max_depth,
+        triangles: mesh.indices.len(),
+        vertex_radial_error,
+        center_radial_error: ErrorStats::from_errors(center_errors),
+        normal_deviation: ErrorStats::from_errors(normal_deviations),
+        open_edges: open_edges(&mesh),
+    }
+}
+
+/// Edges of the welded mesh with a single face, by their sorted vertex indices
+fn open_edges(mesh: &Mesh) -> usize {
+    let mut edge_faces: HashMap<(u32, u32), usize> = HashMap::new();
+    for face in mesh.indices.iter() {
+        for edge in 0..3 {
+            let (a, b) = (face[edge], face[(edge + 1) % 3]);
+            *edge_faces.entry((a.min(b), a.max(b))).or_default() += 1;
+        }
+    }
+    edge_faces.values().filter(|&&faces| faces == 1).count()
+}
+
+/// Distance from the sphere to the line of the ray relative to the radius,
+/// negative when the ray goes through the sphere
+fn sphere_miss_distance(sphere: &Sphere, ray: &Ray) -> f32 {
+    let direction = ray.direction.normalize();
+    let to_center = sphere.center - ray.origin;
+    let closest = to_center - direction * to_center.dot(direction);
+    if to_center.dot(direction) < 0. {
+        return to_center.magnitude() / sphere.radius - 1.;
+    }
+    closest.magnitude() / sphere.radius - 1.
+}
+
+/// Angle between unit vectors, degrees
+fn angle(a: Vector3<f32>, b: Vector3<f32>) -> f32 {
+    let cosine = a.dot(b);
+    // normals of degenerate triangles are NaN, they count as opposite
+    if cosine.is_nan() {
+        return 180.;
+    }
+    cosine.clamp(-1., 1.).acos().to_degrees()
+}
@@ -1,7 +1,44 @@
-use std::ops::{Index, IndexMut};
+use std::{
+    fs::File,
+    io::{BufWriter, Result as IoResult, Write},
+    ops::{Index, IndexMut},
+    path::Path,
+};
 
+#[cfg(feature = "viewer")]
 use glium::{texture::RawImage2d, Display, Texture2d};
 
+/// Region of a buffer, `bottom` is the index of the first row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub left: u32,
+    pub bottom: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// Binary 8-bit PPM (P6)
+    Ppm,
+    /// 8-bit RGB PNG
+    Png,
+    /// 16-bit RGB PNG
+    Png16,
+}
+
+impl ImageFormat {
+    /// Guess format by file extension, `None` for unknown extensions
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Option<ImageFormat> {
+        let extension = path.as_ref().extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "ppm" => Some(ImageFormat::Ppm),
+            "png" => Some(ImageFormat::Png),
+            _ => None,
+        }
+    }
+}
+
 pub struct CPUBuffer {
     data: Vec<[f32; 3]>,
     pub width: u32,
@@ -27,6 +64,7 @@ impl CPUBuffer {
             .collect()
     }
 
+    #[cfg(feature = "viewer")]
     pub fn as_texture(&self, display: &Display) -> Texture2d {
         let raw_image = RawImage2d::from_raw_rgb_reversed(&self.ravel(), (self.width, self.height));
 
@@ -34,6 +72,46 @@ impl CPUBuffer {
 
         dest_texture
     }
+
+    /// Write buffer to file, first row of the buffer is the top of the image
+    pub fn save<P: AsRef<Path>>(&self, path: P, format: ImageFormat) -> IoResult<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        match format {
+            ImageFormat::Ppm => self.write_ppm(&mut writer)?,
+            ImageFormat::Png => self.write_png(&mut writer, png::BitDepth::Eight)?,
+            ImageFormat::Png16 => self.write_png(&mut writer, png::BitDepth::Sixteen)?,
+        }
+        writer.flush()
+    }
+
+    pub fn write_ppm<W: Write>(&self, writer: &mut W) -> IoResult<()> {
+        write!(writer, "P6\n{} {}\n255\n", self.width, self.height)?;
+        writer.write_all(&self.ravel())
+    }
+
+    pub fn write_png<W: Write>(&self, writer: &mut W, bit_depth: png::BitDepth) -> IoResult<()> {
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(bit_depth);
+        let mut png_writer = encoder.write_header()?;
+
+        let image_data = match bit_depth {
+            png::BitDepth::Sixteen => self.ravel_u16(),
+            _ => self.ravel(),
+        };
+        png_writer.write_image_data(&image_data)?;
+        png_writer.finish()?;
+        Ok(())
+    }
+
+    /// Big-endian 16-bit channels, as PNG expects them
+    fn ravel_u16(&self) -> Vec<u8> {
+        self.data
+            .iter()
+            .flatten()
+            .flat_map(|x| ((x.clamp(0., 1.) * 65535.) as u16).to_be_bytes())
+            .collect()
+    }
 }
 
 impl Index<(usize, usize)> for CPUBuffer {
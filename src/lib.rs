@@ -0,0 +1,15 @@
+extern crate cgmath;
+#[cfg(feature = "viewer")]
+extern crate glium;
+
+pub mod accuracy;
+pub mod cpu_buffer;
+pub mod materials;
+pub mod obj;
+pub mod ply;
+pub mod raytracing;
+pub mod scene_file;
+pub mod shapes;
+pub mod utils;
+#[cfg(feature = "viewer")]
+pub mod viewer;
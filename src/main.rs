@@ -1,23 +1,369 @@
-extern crate cgmath;
-extern crate glium;
-
-pub mod cpu_buffer;
-pub mod raytracing;
-pub mod utils;
-mod shapes;
-mod materials;
+use std::{
+    str::FromStr,
+    time::{Duration, Instant},
+};
 
 use cgmath::{Deg, InnerSpace, Rad, Vector3};
-use cpu_buffer::CPUBuffer;
+use curve_ray::accuracy::{self, AccuracyOptions};
+use curve_ray::cpu_buffer::{CPUBuffer, ImageFormat, Rect};
+use curve_ray::obj::{self, ObjOptions};
+use curve_ray::raytracing::{
+    self, camera::Camera, fit::Sphere, mesh::Mesh, renderer::{draw_rect, RendererKind, DEFAULT_TRACE_DEPTH},
+    scene::Scene, tiles::TileScheduler,
+};
+use curve_ray::scene_file::{self, SceneFile};
+use curve_ray::materials::Material;
+use curve_ray::shapes::{self, Shape, Transform};
+#[cfg(feature = "viewer")]
+use curve_ray::raytracing::{progressive::Accumulation, renderer::Renderer};
+#[cfg(feature = "viewer")]
+use curve_ray::viewer;
+#[cfg(feature = "viewer")]
 use glium::glutin;
-use glium::Surface;
-use raytracing::camera::Camera;
+
+
+const USAGE: &str = "\
+usage: curve_ray <command> [scene.json] [options]
+
+commands:
+  render       render once and save, requires -o <image.png|image.ppm>
+  view         render into a window, the default without a command
+  bench        time every renderer on the scene
+  export-mesh  save the triangulation of the patches, requires -o <mesh.obj>
+  accuracy     JSON report of the curve sphere against the analytic sphere, -o <report.json> or stdout
+
+The scene is a JSON scene file or `cornell` for the built-in Cornell box,
+the curve sphere or --mesh by default.
+
+options:
+  -o, --output <path>         output file
+  --width <n>, --height <n>   image size, 1000x500 by default
+  --spp <n>                   samples per pixel, 1 by default
+  --progressive               view: add a sample per pixel every frame until the window closes
+  --renderer <list>           comma separated: triangulated, curve, trihedral, shaded, whitted, path
+  --trace-depth <n>           bounces of reflected, refracted and path rays, 5 by default
+  --threads <n>, --tile-size <n>
+  --bvh                       build BVH over the patches and the triangulation
+  --obb                       cull curve patches by oriented boxes
+  --mesh <path>               curve patches from an OBJ or ASCII PLY mesh instead of the sphere
+  --triangulation <n>         accuracy of the uniform triangulation without a scene file, 5 by default
+  --png16                     save 16-bit PNG, the output must be .png
+  --repeat <n>                bench runs per renderer, 3 by default
+  --tolerance <t>             export-mesh: adaptive welded tessellation with this chordal error
+  --max-depth <n>             export-mesh: subdivision limit of the tessellation, 6 by default
+  --groups                    export-mesh: group per patch
+  --debug-objects             export-mesh: base triangles, pivots and shells of the patches";
+
+/// Name of the built-in Cornell box scene, in place of a scene file
+const CORNELL_SCENE: &str = "cornell";
+
+/// Options of every command that builds the scene
+const SCENE_OPTIONS: [&str; 8] = [
+    "--width",
+    "--height",
+    "--renderer",
+    "--trace-depth",
+    "--bvh",
+    "--obb",
+    "--mesh",
+    "--triangulation",
+];
+
+/// Options taking a value, the rest are switches
+const VALUE_OPTIONS: [&str; 14] = [
+    "-o",
+    "--output",
+    "--width",
+    "--height",
+    "--spp",
+    "--renderer",
+    "--trace-depth",
+    "--threads",
+    "--tile-size",
+    "--mesh",
+    "--triangulation",
+    "--repeat",
+    "--tolerance",
+    "--max-depth",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Command {
+    Render,
+    View,
+    Bench,
+    ExportMesh,
+    Accuracy,
+}
+
+impl Command {
+    const ALL: [Command; 5] = [
+        Command::Render,
+        Command::View,
+        Command::Bench,
+        Command::ExportMesh,
+        Command::Accuracy,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Render => "render",
+            Command::View => "view",
+            Command::Bench => "bench",
+            Command::ExportMesh => "export-mesh",
+            Command::Accuracy => "accuracy",
+        }
+    }
+
+    /// Whether the command accepts the option
+    fn accepts(&self, option: &str) -> bool {
+        let scene = SCENE_OPTIONS.contains(&option);
+        let sampling = ["--spp", "--threads", "--tile-size"].contains(&option);
+        match self {
+            Command::Render => scene || sampling || ["-o", "--output", "--png16"].contains(&option),
+            Command::View => scene || sampling || option == "--progressive",
+            Command::Bench => scene || sampling || option == "--repeat",
+            Command::ExportMesh => [
+                "-o",
+                "--output",
+                "--mesh",
+                "--triangulation",
+                "--tolerance",
+                "--max-depth",
+                "--groups",
+                "--debug-objects",
+            ]
+            .contains(&option),
+            Command::Accuracy => ["-o", "--output"].contains(&option),
+        }
+    }
+}
+
+/// Validated command line
+struct Options {
+    scene: Option<String>,
+    output: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    samples_per_pixel: u32,
+    progressive: bool,
+    renderers: Option<Vec<RendererKind>>,
+    trace_depth: Option<u32>,
+    threads: Option<usize>,
+    tile_size: Option<u32>,
+    bvh: bool,
+    obb: bool,
+    mesh: Option<String>,
+    triangulation: Option<i32>,
+    png16: bool,
+    repeat: usize,
+    tolerance: Option<f32>,
+    max_depth: u32,
+    groups: bool,
+    debug_objects: bool,
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            scene: None,
+            output: None,
+            width: None,
+            height: None,
+            samples_per_pixel: 1,
+            progressive: false,
+            renderers: None,
+            trace_depth: None,
+            threads: None,
+            tile_size: None,
+            bvh: false,
+            obb: false,
+            mesh: None,
+            triangulation: None,
+            png16: false,
+            repeat: 3,
+            tolerance: None,
+            max_depth: 6,
+            groups: false,
+            debug_objects: false,
+        }
+    }
+}
 
 
 fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help" || arg == "help") {
+        println!("{}", USAGE);
+        return;
+    }
+    let (command, options) = match parse_args(&args) {
+        Ok(parsed) => parsed,
+        Err(error) => usage_error(&error),
+    };
+
+    match command {
+        Command::Render => render(&options),
+        Command::View => view_scene(&options),
+        Command::Bench => bench(&options),
+        Command::ExportMesh => export_mesh(&options),
+        Command::Accuracy => accuracy_report(&options),
+    }
+}
+
+
+/// Command and its options. When the first argument is not a command name it is the scene
+/// or an option of `view`, or of `render` when there is an output.
+fn parse_args(args: &[String]) -> Result<(Command, Options), String> {
+    let named = args
+        .first()
+        .and_then(|first| Command::ALL.iter().find(|command| command.name() == first));
+    let (command, rest) = match named {
+        Some(&command) => (command, &args[1..]),
+        None if args.iter().any(|arg| arg == "-o" || arg == "--output") => (Command::Render, args),
+        None => (Command::View, args),
+    };
+
+    let mut options = Options::default();
+    let mut index = 0;
+    while index < rest.len() {
+        let arg = rest[index].as_str();
+        index += 1;
+        if !arg.starts_with('-') {
+            if options.scene.is_some() || command == Command::Accuracy {
+                return Err(format!("unexpected argument `{}`", arg));
+            }
+            options.scene = Some(arg.to_string());
+            continue;
+        }
+        if !Command::ALL.iter().any(|command| command.accepts(arg)) {
+            return Err(format!("unknown option `{}`", arg));
+        }
+        if !command.accepts(arg) {
+            return Err(format!("`{}` is not an option of `{}`", arg, command.name()));
+        }
+
+        let value = if VALUE_OPTIONS.contains(&arg) {
+            match rest.get(index) {
+                Some(value) => {
+                    index += 1;
+                    value.as_str()
+                }
+                None => return Err(format!("{} requires a value", arg)),
+            }
+        } else {
+            ""
+        };
+        match arg {
+            "-o" | "--output" => options.output = Some(value.to_string()),
+            "--width" => options.width = Some(parse_positive(arg, value)?),
+            "--height" => options.height = Some(parse_positive(arg, value)?),
+            "--spp" => options.samples_per_pixel = parse_positive(arg, value)?,
+            "--progressive" => options.progressive = true,
+            "--renderer" => {
+                let renderers = value
+                    .split(',')
+                    .map(|name| name.parse())
+                    .collect::<Result<Vec<RendererKind>, String>>()?;
+                options.renderers = Some(renderers);
+            }
+            "--trace-depth" => options.trace_depth = Some(parse_positive(arg, value)?),
+            "--threads" => options.threads = Some(parse_positive(arg, value)?),
+            "--tile-size" => options.tile_size = Some(parse_positive(arg, value)?),
+            "--bvh" => options.bvh = true,
+            "--obb" => options.obb = true,
+            "--mesh" => options.mesh = Some(value.to_string()),
+            "--triangulation" => options.triangulation = Some(parse_positive(arg, value)?),
+            "--png16" => options.png16 = true,
+            "--repeat" => options.repeat = parse_positive(arg, value)?,
+            "--tolerance" => options.tolerance = Some(parse_positive(arg, value)?),
+            "--max-depth" => options.max_depth = parse_positive(arg, value)?,
+            "--groups" => options.groups = true,
+            "--debug-objects" => options.debug_objects = true,
+            _ => return Err(format!("unknown option `{}`", arg)),
+        }
+    }
+
+    if options.scene.is_some() && (options.mesh.is_some() || options.triangulation.is_some()) {
+        return Err("--mesh and --triangulation can't be used with a scene".to_string());
+    }
+    let requires_output = matches!(command, Command::Render | Command::ExportMesh);
+    if requires_output && options.output.is_none() {
+        return Err(format!("`{}` requires -o <path>", command.name()));
+    }
+    Ok((command, options))
+}
+
+
+/// Number above zero, zero and malformed numbers are errors
+fn parse_positive<T>(option: &str, value: &str) -> Result<T, String>
+where
+    T: FromStr + PartialOrd + Default,
+{
+    match value.parse::<T>() {
+        Ok(number) if number > T::default() => Ok(number),
+        _ => Err(format!("{} requires a positive number, got `{}`", option, value)),
+    }
+}
+
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("{}\n\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    std::process::exit(1);
+}
+
+
+/// Scene file, the Cornell box or the default scene with the size, renderers and acceleration of the options
+fn load_scene(options: &Options) -> SceneFile {
+    let mut scene_file = match options.scene.as_ref() {
+        Some(name) if name == CORNELL_SCENE => cornell_scene(),
+        Some(path) => scene_file::load_scene_file(path)
+            .unwrap_or_else(|error| fail(&format!("can't load {}", error))),
+        None => default_scene(options),
+    };
+
+    if let Some(width) = options.width {
+        scene_file.width = width;
+    }
+    if let Some(height) = options.height {
+        scene_file.height = height;
+    }
+    if let Some(renderers) = options.renderers.as_ref() {
+        scene_file.renderers = renderers.clone();
+    }
+    if let Some(trace_depth) = options.trace_depth {
+        scene_file.trace_depth = trace_depth;
+    }
+    // strip of every renderer
+    scene_file.camera.ratio =
+        scene_file.width as f32 / scene_file.renderers.len() as f32 / scene_file.height as f32;
+
+    if options.obb {
+        for shape in scene_file.scene.shapes.iter_mut() {
+            for part in shape.patches.iter_mut() {
+                part.precalc_obb();
+            }
+        }
+    }
+    if options.bvh {
+        scene_file.scene.build_bvh();
+    }
+    scene_file
+}
+
+
+/// Curve sphere or `--mesh <path>` in front of the camera, triangulated and trihedral renderers
+fn default_scene(options: &Options) -> SceneFile {
+    let renderers = vec![RendererKind::Triangulated, RendererKind::Trihedral];
+
     let width: u32 = 1000;
     let height: u32 = 500;
-    let mut cpu_buffer = CPUBuffer::new(width, height);
     let camera = Camera {
         origin: Vector3 {
             x: 0.,
@@ -26,47 +372,277 @@ fn main() {
         },
         direction: Vector3::new(0., 0., 1.).normalize(),
         fov: Rad::from(Deg(90.)).0,
-        ratio: width as f32 / 2. / height as f32,
+        ratio: width as f32 / renderers.len() as f32 / height as f32,
     };
-    let mut shape = get_sphere();
-    for part in shape.iter_mut() {
-        part.triangulate(5)
+    let mut patches = match options.mesh.as_ref() {
+        Some(path) => shapes::load_curve_mesh(path)
+            .unwrap_or_else(|error| fail(&format!("can't load {}: {}", path, error))),
+        None => shapes::get_curve_sphere(),
+    };
+    for part in patches.iter_mut() {
+        part.triangulate(options.triangulation.unwrap_or(5));
     }
+    let shape = Shape::new(patches, Material::default(), Transform::identity());
+
+    SceneFile {
+        width,
+        height,
+        camera,
+        renderers,
+        trace_depth: DEFAULT_TRACE_DEPTH,
+        scene: Scene::new(vec![shape], false),
+    }
+}
+
+
+/// Cornell box with its light, shaded renderer
+fn cornell_scene() -> SceneFile {
+    let renderers = vec![RendererKind::Shaded];
 
+    let width: u32 = 600;
+    let height: u32 = 600;
+    let mut shapes = shapes::get_cornell_box();
+    for shape in shapes.iter_mut() {
+        for part in shape.patches.iter_mut() {
+            part.triangulate(5);
+        }
+    }
+    let mut scene = Scene::new(shapes, false);
+    scene.lights.push(shapes::get_cornell_box_light());
+
+    SceneFile {
+        width,
+        height,
+        camera: shapes::get_cornell_box_camera(width as f32 / renderers.len() as f32 / height as f32),
+        renderers,
+        trace_depth: DEFAULT_TRACE_DEPTH,
+        scene,
+    }
+}
+
+
+fn scheduler(options: &Options) -> TileScheduler {
+    let mut scheduler = TileScheduler::default();
+    if let Some(threads) = options.threads {
+        scheduler.threads = threads;
+    }
+    if let Some(tile_size) = options.tile_size {
+        scheduler.tile_size = tile_size;
+    }
+    scheduler
+}
+
+
+/// `curve_ray render scene.json -o image.png` renders once and exits
+fn render(options: &Options) {
+    let path = options.output.as_ref().expect("render requires output");
+    let format = match (ImageFormat::from_path(path), options.png16) {
+        (None, _) => usage_error(&format!("unknown image format of {}, expected .png or .ppm", path)),
+        (Some(ImageFormat::Png), true) => ImageFormat::Png16,
+        (Some(_), true) => usage_error(&format!("--png16 requires a .png output, got {}", path)),
+        (Some(format), false) => format,
+    };
+
+    let scene_file = load_scene(options);
+    let mut cpu_buffer = CPUBuffer::new(scene_file.width, scene_file.height);
+    let times = raytracing::draw_to(
+        &mut cpu_buffer,
+        &scene_file.camera,
+        &scene_file.scene,
+        &scene_file.renderers,
+        &scheduler(options),
+        options.samples_per_pixel,
+        scene_file.trace_depth,
+    );
+    print_times(&scene_file.renderers, &times);
+    if let Err(error) = cpu_buffer.save(path, format) {
+        fail(&format!("can't save {}: {}", path, error));
+    }
+}
+
+
+/// Render time of every renderer of `draw_to`
+fn print_times(renderers: &[RendererKind], times: &[Duration]) {
+    for (kind, time) in renderers.iter().zip(times.iter()) {
+        println!("{} ended in {:?}", kind, time);
+    }
+}
+
+
+fn view_scene(options: &Options) {
+    #[cfg(feature = "viewer")]
+    {
+        view(
+            load_scene(options),
+            scheduler(options),
+            options.samples_per_pixel,
+            options.progressive,
+        );
+    }
+
+    #[cfg(not(feature = "viewer"))]
+    {
+        let _ = options;
+        usage_error("built without the `viewer` feature, use `render -o <path>`");
+    }
+}
+
+
+/// Time of every renderer over the whole image, the fastest and the mean of the runs
+fn bench(options: &Options) {
+    let mut scene_file = load_scene(options);
+    scene_file.camera.ratio = scene_file.width as f32 / scene_file.height as f32;
+    let mut cpu_buffer = CPUBuffer::new(scene_file.width, scene_file.height);
+    let rect = Rect {
+        left: 0,
+        bottom: 0,
+        width: scene_file.width,
+        height: scene_file.height,
+    };
+    let scheduler = scheduler(options);
+    let rays = (scene_file.width * scene_file.height * options.samples_per_pixel) as f64;
+    let scene = &scene_file.scene;
+    println!(
+        "{} shapes, {} patches, {} triangles",
+        scene.shapes.len(),
+        scene.shapes.iter().map(|shape| shape.patches.len()).sum::<usize>(),
+        scene.triangulation.indices.len(),
+    );
+    if scene_file.scene.has_bvh() {
+        // built again to time it, the trees are the same
+        let start = Instant::now();
+        scene_file.scene.build_bvh();
+        println!("BVH built in {:?}", start.elapsed());
+    }
+
+    for kind in scene_file.renderers.iter() {
+        let renderer = kind.renderer(scene_file.trace_depth);
+        let mut times = Vec::with_capacity(options.repeat);
+        for _ in 0..options.repeat {
+            let start = Instant::now();
+            draw_rect(
+                &mut cpu_buffer,
+                &rect,
+                &scene_file.camera,
+                &scene_file.scene,
+                renderer.as_ref(),
+                &scheduler,
+                options.samples_per_pixel,
+            );
+            times.push(start.elapsed().as_secs_f64());
+        }
+        let fastest = times.iter().copied().fold(f64::INFINITY, f64::min);
+        let mean = times.iter().sum::<f64>() / times.len() as f64;
+        println!(
+            "{:>12}: fastest {:.3} s, mean {:.3} s over {} runs, {:.2} Mrays/s",
+            kind.name(),
+            fastest,
+            mean,
+            times.len(),
+            rays / fastest / 1e6,
+        );
+    }
+}
+
+
+/// Uniform triangulation of the shapes, or adaptive tessellation welded per shape with `--tolerance`
+fn export_mesh(options: &Options) {
+    let path = options.output.as_ref().expect("export-mesh requires output");
+    if !path.to_ascii_lowercase().ends_with(".obj") {
+        usage_error(&format!("unknown mesh format of {}, expected .obj", path));
+    }
+    let scene_file = load_scene(options);
+    let shapes = &scene_file.scene.shapes;
+
+    let result = match options.tolerance {
+        Some(tolerance) => {
+            let mut mesh = Mesh::default();
+            let mut patch_offset = 0;
+            for shape in shapes.iter() {
+                let tessellation = Mesh::tessellate(&shape.patches, tolerance, options.max_depth);
+                mesh.append(&shape.transform.mesh(&tessellation), patch_offset);
+                patch_offset += shape.patches.len();
+            }
+            obj::save_mesh(path, &mesh)
+        }
+        None => obj::save_shapes(
+            path,
+            shapes,
+            &ObjOptions {
+                group_per_patch: options.groups,
+                debug_objects: options.debug_objects,
+            },
+        ),
+    };
+    if let Err(error) = result {
+        fail(&format!("can't save {}: {}", path, error));
+    }
+}
+
+
+/// Accuracy of the curve sphere to the file of `-o` or stdout
+fn accuracy_report(options: &Options) {
+    let sphere = Sphere {
+        center: Vector3::new(0., 0., 0.),
+        radius: 1.,
+    };
+    let report = accuracy::sphere_report(
+        &mut shapes::get_curve_sphere(),
+        &sphere,
+        &AccuracyOptions::default(),
+    );
+    match options.output.as_ref() {
+        Some(path) if path != "-" => {
+            if let Err(error) = std::fs::write(path, report.to_json()) {
+                fail(&format!("can't save {}: {}", path, error));
+            }
+        }
+        _ => println!("{}", report.to_json()),
+    }
+}
+
+
+#[cfg(feature = "viewer")]
+fn view(
+    scene_file: SceneFile,
+    scheduler: TileScheduler,
+    samples_per_pixel: u32,
+    progressive: bool,
+) {
+    let SceneFile {
+        width,
+        height,
+        camera,
+        renderers,
+        trace_depth,
+        scene,
+    } = scene_file;
+    let mut cpu_buffer = CPUBuffer::new(width, height);
     // init window
-    let (display, event_loop) = utils::init_window(width, height);
+    let (display, event_loop) = viewer::init_window(cpu_buffer.width, cpu_buffer.height);
     // dest_texture.as_surface().clear_color(0.0, 0.5, 0.3, 1.0);
 
     // start draw
     // procese mouse move
 
-    // Here we draw the black background and triangle to the screen using the previously
-    // initialised resources.
-    //
-    // In this case we use a closure for simplicity, however keep in mind that most serious
-    // applications should probably use a function that takes the resources as an argument.
-    let to_screen = |cpu_buffer: &CPUBuffer, display: &glium::Display| {
-        // drawing a frame
-        let mut target = display.draw();
-        target.clear_color(0.0, 0.5, 0.3, 1.0);
-        cpu_buffer
-            .as_texture(&display)
-            .as_surface()
-            .fill(&target, glium::uniforms::MagnifySamplerFilter::Linear);
-
-        // this is for shaders
-        //
-        // target.draw(
-        //     &vertex_buffer, &index_buffer, &program,
-        //     &uniforms, &Default::default()
-        // ).unwrap();
-        target.finish().unwrap();
-    };
-    // println!("draw");
-
-    // // Draw the triangle to the screen.
-    raytracing::draw_to(&mut cpu_buffer, &camera, &mut shape);
-    to_screen(&cpu_buffer, &display);
+    // passes are added every frame in the progressive mode
+    let mut accumulation = progressive.then(|| Accumulation::new(width, height));
+    let renderer_boxes: Vec<Box<dyn Renderer>> =
+        renderers.iter().map(|kind| kind.renderer(trace_depth)).collect();
+    if accumulation.is_none() {
+        // // Draw the triangle to the screen.
+        let times = raytracing::draw_to(
+            &mut cpu_buffer,
+            &camera,
+            &scene,
+            &renderers,
+            &scheduler,
+            samples_per_pixel,
+            trace_depth,
+        );
+        print_times(&renderers, &times);
+        viewer::to_screen(&cpu_buffer, &display);
+    }
 
     // the main loop
     event_loop.run(move |event, _, control_flow| {
@@ -78,7 +654,7 @@ fn main() {
                 glutin::event::WindowEvent::Resized(..) => {
                     println!("resized!");
                     // draw();
-                    to_screen(&cpu_buffer, &display);
+                    viewer::to_screen(&cpu_buffer, &display);
                     glutin::event_loop::ControlFlow::Poll
                 }
                 _ => glutin::event_loop::ControlFlow::Poll, // glutin::event_loop::ControlFlow::Poll,
@@ -87,6 +663,18 @@ fn main() {
                 // draw();
                 glutin::event_loop::ControlFlow::Poll
             }
+            glutin::event::Event::MainEventsCleared => {
+                if let Some(accumulation) = accumulation.as_mut() {
+                    accumulation.add_pass(&camera, &scene, &renderer_boxes, &scheduler);
+                    accumulation.resolve(&mut cpu_buffer);
+                    viewer::to_screen(&cpu_buffer, &display);
+                    display
+                        .gl_window()
+                        .window()
+                        .set_title(&format!("CurveRay, {} passes", accumulation.passes));
+                }
+                glutin::event_loop::ControlFlow::Poll
+            }
             _ => glutin::event_loop::ControlFlow::Poll,
         };
     });
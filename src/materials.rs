@@ -1,20 +1,43 @@
-type RGB = [f32; 3];
-type RGBA = [f32; 4];
+pub type RGB = [f32; 3];
+pub type RGBA = [f32; 4];
 
 
-const RED: RGB = [1.0, 0.0, 0.0];
-const GREEN: RGB = [0.0, 1.0, 0.0];
-const BLUE: RGB = [0.0, 0.0, 1.0];
-const YELLOW: RGB = [1.0, 1.0, 0.0];
-const CYAN: RGB = [0.0, 1.0, 1.0];
-const MAGENTA: RGB = [1.0, 0.0, 1.0];
-const WHITE: RGB = [1.0, 1.0, 1.0];
-const BLACK: RGB = [0.0, 0.0, 0.0];
+pub const RED: RGB = [1.0, 0.0, 0.0];
+pub const GREEN: RGB = [0.0, 1.0, 0.0];
+pub const BLUE: RGB = [0.0, 0.0, 1.0];
+pub const YELLOW: RGB = [1.0, 1.0, 0.0];
+pub const CYAN: RGB = [0.0, 1.0, 1.0];
+pub const MAGENTA: RGB = [1.0, 0.0, 1.0];
+pub const WHITE: RGB = [1.0, 1.0, 1.0];
+pub const BLACK: RGB = [0.0, 0.0, 0.0];
 
 
-struct Material {
-    color: RGB,
-    alpha: f32,
-    refraction_koefficient: f32,
-    diffusion_koefficient: f32
-}
\ No newline at end of file
+#[derive(Debug, Clone, Copy)]
+pub struct Material {
+    pub color: RGB,
+    pub alpha: f32,
+    pub refraction_koefficient: f32,
+    /// Part of the reflected light scattered by Lambert, the rest is the Blinn-Phong highlight
+    pub diffusion_koefficient: f32,
+    /// Exponent of the Blinn-Phong highlight, higher is smaller and sharper
+    pub shininess: f32,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Material {
+            color: WHITE,
+            alpha: 1.,
+            refraction_koefficient: 1.,
+            diffusion_koefficient: 1.,
+            shininess: 32.,
+        }
+    }
+}
+
+impl Material {
+    /// Part of the reflected light in the highlight
+    pub fn specular_koefficient(&self) -> f32 {
+        (1. - self.diffusion_koefficient).clamp(0., 1.)
+    }
+}
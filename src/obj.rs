@@ -0,0 +1,356 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Error as IoError, ErrorKind, Result as IoResult, Write},
+    path::Path,
+};
+
+use cgmath::{InnerSpace, Vector2, Vector3, Zero};
+
+use crate::raytracing::{curve_triangle::CurveTriangle, mesh::Mesh, triangle::Triangle};
+use crate::shapes::{Shape, Transform};
+
+/// What to write besides the triangulation of the patches
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ObjOptions {
+    /// Group `patch_N` per patch
+    pub group_per_patch: bool,
+    /// Base triangle, pivots and shell of every patch as separate objects
+    pub debug_objects: bool,
+}
+
+/// Wavefront OBJ writer, keeps 1-based indices of written vertexes, uvs and normals
+pub struct ObjWriter<W: Write> {
+    writer: W,
+    vertexes: usize,
+    uvs: usize,
+    normals: usize,
+}
+
+impl<W: Write> ObjWriter<W> {
+    pub fn new(writer: W) -> ObjWriter<W> {
+        ObjWriter {
+            writer,
+            vertexes: 0,
+            uvs: 0,
+            normals: 0,
+        }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// `CurveTriangle::triangulate` output of the patches, normals are taken from the curve surface.
+    /// Equal vertexes of a patch are written once.
+    pub fn write_patches(&mut self, patches: &[CurveTriangle], options: &ObjOptions) -> IoResult<()> {
+        let identity = Transform::identity();
+        writeln!(self.writer, "o curve_surface")?;
+        for (index, patch) in patches.iter().enumerate() {
+            if options.group_per_patch {
+                writeln!(self.writer, "g patch_{}", index)?;
+            }
+            self.write_triangulation(patch, &identity)?;
+        }
+
+        if options.debug_objects {
+            for (index, patch) in patches.iter().enumerate() {
+                self.write_debug_objects(index, patch, &identity)?;
+            }
+        }
+        self.writer.flush()
+    }
+
+    /// Same as `write_patches` in the world coords, object `shape_N` per shape.
+    /// Patches are numbered through all shapes.
+    pub fn write_shapes(&mut self, shapes: &[Shape], options: &ObjOptions) -> IoResult<()> {
+        let mut index = 0;
+        for (shape_id, shape) in shapes.iter().enumerate() {
+            writeln!(self.writer, "o shape_{}", shape_id)?;
+            for patch in shape.patches.iter() {
+                if options.group_per_patch {
+                    writeln!(self.writer, "g patch_{}", index)?;
+                }
+                self.write_triangulation(patch, &shape.transform)?;
+                index += 1;
+            }
+        }
+
+        if options.debug_objects {
+            let mut index = 0;
+            for shape in shapes.iter() {
+                for patch in shape.patches.iter() {
+                    self.write_debug_objects(index, patch, &shape.transform)?;
+                    index += 1;
+                }
+            }
+        }
+        self.writer.flush()
+    }
+
+    /// Welded mesh, with normals and uvs if the mesh has them
+    pub fn write_mesh(&mut self, mesh: &Mesh) -> IoResult<()> {
+        writeln!(self.writer, "o mesh")?;
+        let first_vertex = self.vertexes + 1;
+        for &vertex in mesh.vertexes.iter() {
+            self.write_vertex(vertex)?;
+        }
+        let first_uv = self.uvs + 1;
+        for &uv in mesh.uvs.iter().flatten() {
+            self.write_uv(uv)?;
+        }
+        let first_normal = self.normals + 1;
+        for &normal in mesh.normals.iter().flatten() {
+            self.write_normal(normal)?;
+        }
+
+        for face in mesh.indices.iter() {
+            let [a, b, c] = face.map(|index| {
+                let index = index as usize;
+                let vertex = first_vertex + index;
+                match (mesh.uvs.is_some(), mesh.normals.is_some()) {
+                    (true, true) => format!("{}/{}/{}", vertex, first_uv + index, first_normal + index),
+                    (true, false) => format!("{}/{}", vertex, first_uv + index),
+                    (false, true) => format!("{}//{}", vertex, first_normal + index),
+                    (false, false) => vertex.to_string(),
+                }
+            });
+            writeln!(self.writer, "f {} {} {}", a, b, c)?;
+        }
+        self.writer.flush()
+    }
+
+    fn write_triangulation(&mut self, patch: &CurveTriangle, transform: &Transform) -> IoResult<()> {
+        // vertex index, normal index and normal by the vertex position
+        let mut written: HashMap<[u32; 3], (usize, usize, Vector3<f32>)> = HashMap::new();
+        for triangle in patch.triangulation.iter() {
+            let mut face = [(0, 0, Vector3::zero()); 3];
+            for (corner, &vertex) in triangle.vertexes.iter().enumerate() {
+                let key = vertex.map(f32::to_bits).into();
+                face[corner] = match written.get(&key) {
+                    Some(&written) => written,
+                    None => {
+                        let normal = match patch.project_to_base(vertex) {
+                            Some(bary) => patch.hit_normal_at_bary(bary),
+                            None => triangle.normal(),
+                        };
+                        self.write_vertex(transform.point(vertex))?;
+                        self.write_normal(transform.direction(normal))?;
+                        written.insert(key, (self.vertexes, self.normals, normal));
+                        (self.vertexes, self.normals, normal)
+                    }
+                };
+            }
+            // counter-clockwise winding looking against the normals
+            if triangle.normal().dot(face[0].2 + face[1].2 + face[2].2) < 0. {
+                face.swap(1, 2);
+            }
+            writeln!(
+                self.writer,
+                "f {}//{} {}//{} {}//{}",
+                face[0].0, face[0].1, face[1].0, face[1].1, face[2].0, face[2].1,
+            )?;
+        }
+        Ok(())
+    }
+
+    fn write_debug_objects(&mut self, index: usize, patch: &CurveTriangle, transform: &Transform) -> IoResult<()> {
+        writeln!(self.writer, "o patch_{}_base", index)?;
+        self.write_triangle(&patch.base, transform)?;
+
+        writeln!(self.writer, "o patch_{}_pivots", index)?;
+        for &pivot in patch.pivots.iter() {
+            self.write_vertex(transform.point(pivot))?;
+        }
+        writeln!(
+            self.writer,
+            "p {} {} {}",
+            self.vertexes - 2,
+            self.vertexes - 1,
+            self.vertexes,
+        )?;
+
+        if let Some(shell) = patch.tr_shell.as_ref() {
+            writeln!(self.writer, "o patch_{}_shell", index)?;
+            for triangle in shell.triangles.iter() {
+                self.write_triangle(triangle, transform)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_triangle(&mut self, triangle: &Triangle, transform: &Transform) -> IoResult<()> {
+        for &vertex in triangle.vertexes.iter() {
+            self.write_vertex(transform.point(vertex))?;
+        }
+        writeln!(
+            self.writer,
+            "f {} {} {}",
+            self.vertexes - 2,
+            self.vertexes - 1,
+            self.vertexes,
+        )
+    }
+
+    fn write_vertex(&mut self, vertex: Vector3<f32>) -> IoResult<()> {
+        self.vertexes += 1;
+        writeln!(self.writer, "v {} {} {}", vertex.x, vertex.y, vertex.z)
+    }
+
+    fn write_uv(&mut self, uv: Vector2<f32>) -> IoResult<()> {
+        self.uvs += 1;
+        writeln!(self.writer, "vt {} {}", uv.x, uv.y)
+    }
+
+    fn write_normal(&mut self, normal: Vector3<f32>) -> IoResult<()> {
+        self.normals += 1;
+        writeln!(self.writer, "vn {} {} {}", normal.x, normal.y, normal.z)
+    }
+}
+
+/// Write triangulation of the patches to an OBJ file, see `ObjWriter::write_patches`
+pub fn save_patches<P: AsRef<Path>>(path: P, patches: &[CurveTriangle], options: &ObjOptions) -> IoResult<()> {
+    ObjWriter::new(BufWriter::new(File::create(path)?)).write_patches(patches, options)
+}
+
+/// Write triangulation of the shapes to an OBJ file, see `ObjWriter::write_shapes`
+pub fn save_shapes<P: AsRef<Path>>(path: P, shapes: &[Shape], options: &ObjOptions) -> IoResult<()> {
+    ObjWriter::new(BufWriter::new(File::create(path)?)).write_shapes(shapes, options)
+}
+
+/// Write the mesh to an OBJ file, see `ObjWriter::write_mesh`
+pub fn save_mesh<P: AsRef<Path>>(path: P, mesh: &Mesh) -> IoResult<()> {
+    ObjWriter::new(BufWriter::new(File::create(path)?)).write_mesh(mesh)
+}
+
+/// Read triangles of an OBJ file, polygons are split into fans.
+/// Vertexes with different normals or texture coords are different vertexes of the mesh.
+/// Normals and uvs are kept only when every face vertex has them.
+pub fn read_obj<R: BufRead>(reader: R) -> IoResult<Mesh> {
+    let (mut positions, mut normals, mut uvs) = (Vec::new(), Vec::new(), Vec::new());
+    let mut mesh = Mesh::default();
+    let (mut mesh_normals, mut mesh_uvs) = (Vec::new(), Vec::new());
+    let (mut all_normals, mut all_uvs) = (true, true);
+    // (position, uv, normal) indices to the mesh vertex
+    let mut vertex_ids: HashMap<(usize, Option<usize>, Option<usize>), u32> = HashMap::new();
+
+    for (number, line) in reader.lines().enumerate() {
+        let line = line?;
+        let line_number = number + 1;
+        let mut tokens = line.split_whitespace();
+        match tokens.next() {
+            Some("v") => positions.push(parse_vector(&mut tokens, line_number)?),
+            Some("vn") => {
+                let normal = parse_vector(&mut tokens, line_number)?;
+                normals.push(unit_normal(normal, line_number)?);
+            }
+            Some("vt") => {
+                let u = parse_float(tokens.next(), line_number)?;
+                // v is optional, but not malformed
+                let v = match tokens.next() {
+                    Some(token) => parse_float(Some(token), line_number)?,
+                    None => 0.,
+                };
+                uvs.push(Vector2::new(u, v));
+            }
+            Some("f") => {
+                let mut face = Vec::new();
+                for corner in tokens {
+                    let mut parts = corner.split('/');
+                    let position = parse_index(parts.next(), positions.len(), "vertex", line_number)?;
+                    let uv = match parts.next() {
+                        Some("") | None => None,
+                        index => Some(parse_index(index, uvs.len(), "texture coord", line_number)?),
+                    };
+                    let normal = match parts.next() {
+                        Some("") | None => None,
+                        index => Some(parse_index(index, normals.len(), "normal", line_number)?),
+                    };
+                    all_uvs &= uv.is_some();
+                    all_normals &= normal.is_some();
+
+                    let id = *vertex_ids.entry((position, uv, normal)).or_insert_with(|| {
+                        mesh.vertexes.push(positions[position]);
+                        mesh_uvs.push(uv.map_or(Vector2::new(0., 0.), |uv| uvs[uv]));
+                        mesh_normals.push(normal.map_or(Vector3::zero(), |normal| normals[normal]));
+                        (mesh.vertexes.len() - 1) as u32
+                    });
+                    face.push(id);
+                }
+                if face.len() < 3 {
+                    return Err(invalid_data(
+                        line_number,
+                        &format!("face needs at least 3 vertexes, got {}", face.len()),
+                    ));
+                }
+                for index in 1..face.len() - 1 {
+                    mesh.indices.push([face[0], face[index], face[index + 1]]);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if all_normals && !mesh.indices.is_empty() {
+        mesh.normals = Some(mesh_normals.into_iter().map(|normal| normal.normalize()).collect());
+    }
+    if all_uvs && !mesh.indices.is_empty() {
+        mesh.uvs = Some(mesh_uvs);
+    }
+    Ok(mesh)
+}
+
+/// Read a mesh from an OBJ file, see `read_obj`
+pub fn load_obj<P: AsRef<Path>>(path: P) -> IoResult<Mesh> {
+    read_obj(BufReader::new(File::open(path)?))
+}
+
+pub(crate) fn invalid_data(line_number: usize, message: &str) -> IoError {
+    IoError::new(ErrorKind::InvalidData, format!("line {}: {}", line_number, message))
+}
+
+pub(crate) fn parse_float(token: Option<&str>, line_number: usize) -> IoResult<f32> {
+    match token {
+        Some(token) => token
+            .parse()
+            .map_err(|_| invalid_data(line_number, &format!("expected a number, got `{}`", token))),
+        None => Err(invalid_data(line_number, "expected a number")),
+    }
+}
+
+/// Normal scaled to the unit length, zero and infinite normals have no direction
+pub(crate) fn unit_normal(normal: Vector3<f32>, line_number: usize) -> IoResult<Vector3<f32>> {
+    let normal = normal.normalize();
+    if normal.x.is_finite() && normal.y.is_finite() && normal.z.is_finite() {
+        Ok(normal)
+    } else {
+        Err(invalid_data(line_number, "normal must have a direction"))
+    }
+}
+
+fn parse_vector<'a, I>(tokens: &mut I, line_number: usize) -> IoResult<Vector3<f32>>
+where
+    I: Iterator<Item = &'a str>,
+{
+    Ok(Vector3::new(
+        parse_float(tokens.next(), line_number)?,
+        parse_float(tokens.next(), line_number)?,
+        parse_float(tokens.next(), line_number)?,
+    ))
+}
+
+/// 1-based index of the `kind` element, negative indices count back from the last element
+fn parse_index(token: Option<&str>, count: usize, kind: &str, line_number: usize) -> IoResult<usize> {
+    let token = token.unwrap_or_default();
+    let index: i64 = token
+        .parse()
+        .map_err(|_| invalid_data(line_number, &format!("expected a {} index, got `{}`", kind, token)))?;
+    let resolved = if index < 0 { count as i64 + index } else { index - 1 };
+    if resolved < 0 || resolved >= count as i64 {
+        return Err(invalid_data(
+            line_number,
+            &format!("{} index {} out of range of {} defined", kind, index, count),
+        ));
+    }
+    Ok(resolved as usize)
+}
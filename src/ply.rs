@@ -0,0 +1,203 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, BufWriter, Error as IoError, ErrorKind, Result as IoResult, Write},
+    path::Path,
+};
+
+use cgmath::{Vector2, Vector3};
+
+use crate::{
+    obj::{invalid_data, parse_float, unit_normal},
+    raytracing::mesh::Mesh,
+};
+
+/// Element of the header with its properties, list properties are only expected in faces
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<String>,
+}
+
+/// Read triangles of an ASCII PLY file, polygons are split into fans.
+/// Vertex properties `nx ny nz` become normals and `u v` or `s t` become uvs.
+pub fn read_ply<R: BufRead>(reader: R) -> IoResult<Mesh> {
+    let mut lines = reader.lines().enumerate().map(|(number, line)| (number + 1, line));
+
+    // header
+    let mut elements: Vec<Element> = Vec::new();
+    let mut has_format = false;
+    match lines.next() {
+        Some((_, Ok(line))) if line.trim() == "ply" => {}
+        _ => return Err(invalid_data(1, "expected `ply`, the file is not a PLY")),
+    }
+    loop {
+        let (line_number, line) = match lines.next() {
+            Some((line_number, line)) => (line_number, line?),
+            None => return Err(IoError::new(ErrorKind::UnexpectedEof, "unexpected end of the header")),
+        };
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["format", "ascii", ..] => has_format = true,
+            ["format", format, ..] => {
+                return Err(invalid_data(
+                    line_number,
+                    &format!("only ascii PLY is supported, got {}", format),
+                ))
+            }
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| {
+                    invalid_data(
+                        line_number,
+                        &format!("expected the count of element `{}`, got `{}`", name, count),
+                    )
+                })?,
+                properties: Vec::new(),
+            }),
+            ["element", ..] => return Err(invalid_data(line_number, "expected `element <name> <count>`")),
+            ["property", _, .., name] => match elements.last_mut() {
+                Some(element) => element.properties.push(name.to_string()),
+                None => {
+                    return Err(invalid_data(
+                        line_number,
+                        &format!("property `{}` before any element", name),
+                    ))
+                }
+            },
+            ["property", ..] => return Err(invalid_data(line_number, "expected `property <type> <name>`")),
+            ["end_header"] if !has_format => {
+                return Err(invalid_data(line_number, "expected `format ascii 1.0` before `end_header`"))
+            }
+            ["end_header"] => break,
+            _ => {}
+        }
+    }
+
+    let mut mesh = Mesh::default();
+    let (mut normals, mut uvs) = (Vec::new(), Vec::new());
+    let mut has_normals = false;
+    let mut has_uvs = false;
+
+    for element in elements.iter() {
+        let column = |name: &str| element.properties.iter().position(|property| property == name);
+        let columns = |names: [&str; 2]| names.map(column);
+
+        for _ in 0..element.count {
+            let (line_number, line) = match lines.next() {
+                Some((line_number, line)) => (line_number, line?),
+                None => return Err(IoError::new(ErrorKind::UnexpectedEof, "unexpected end of the file")),
+            };
+            let values: Vec<&str> = line.split_whitespace().collect();
+            let value = |index: usize| parse_float(values.get(index).copied(), line_number);
+
+            match element.name.as_str() {
+                "vertex" => {
+                    let [x, y, z] = ["x", "y", "z"].map(column);
+                    match (x, y, z) {
+                        (Some(x), Some(y), Some(z)) => {
+                            mesh.vertexes.push(Vector3::new(value(x)?, value(y)?, value(z)?))
+                        }
+                        _ => return Err(invalid_data(line_number, "vertex needs x, y and z")),
+                    }
+                    if let [Some(nx), Some(ny), Some(nz)] = ["nx", "ny", "nz"].map(column) {
+                        has_normals = true;
+                        let normal = Vector3::new(value(nx)?, value(ny)?, value(nz)?);
+                        normals.push(unit_normal(normal, line_number)?);
+                    }
+                    let uv_columns = match columns(["u", "v"]) {
+                        [Some(u), Some(v)] => Some((u, v)),
+                        _ => match columns(["s", "t"]) {
+                            [Some(s), Some(t)] => Some((s, t)),
+                            _ => None,
+                        },
+                    };
+                    if let Some((u, v)) = uv_columns {
+                        has_uvs = true;
+                        uvs.push(Vector2::new(value(u)?, value(v)?));
+                    }
+                }
+                "face" => {
+                    // the vertex list goes first
+                    let count: usize = values
+                        .first()
+                        .and_then(|count| count.parse().ok())
+                        .ok_or_else(|| invalid_data(line_number, "expected vertex count"))?;
+                    let face: Vec<u32> = values
+                        .iter()
+                        .skip(1)
+                        .take(count)
+                        .map(|index| index.parse::<u32>())
+                        .collect::<Result<_, _>>()
+                        .map_err(|_| invalid_data(line_number, "expected vertex index"))?;
+                    if face.len() < 3 || face.len() != count {
+                        return Err(invalid_data(line_number, "face needs at least 3 vertexes"));
+                    }
+                    if face.iter().any(|&index| index as usize >= mesh.vertexes.len()) {
+                        return Err(invalid_data(line_number, "index out of range"));
+                    }
+                    for index in 1..face.len() - 1 {
+                        mesh.indices.push([face[0], face[index], face[index + 1]]);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if has_normals {
+        mesh.normals = Some(normals);
+    }
+    if has_uvs {
+        mesh.uvs = Some(uvs);
+    }
+    Ok(mesh)
+}
+
+/// Write the mesh as ASCII PLY, with normals and uvs if the mesh has them
+pub fn write_ply<W: Write>(mut writer: W, mesh: &Mesh) -> IoResult<()> {
+    writeln!(writer, "ply")?;
+    writeln!(writer, "format ascii 1.0")?;
+    writeln!(writer, "element vertex {}", mesh.vertexes.len())?;
+    for name in ["x", "y", "z"] {
+        writeln!(writer, "property float {}", name)?;
+    }
+    if mesh.normals.is_some() {
+        for name in ["nx", "ny", "nz"] {
+            writeln!(writer, "property float {}", name)?;
+        }
+    }
+    if mesh.uvs.is_some() {
+        for name in ["u", "v"] {
+            writeln!(writer, "property float {}", name)?;
+        }
+    }
+    writeln!(writer, "element face {}", mesh.indices.len())?;
+    writeln!(writer, "property list uchar uint vertex_indices")?;
+    writeln!(writer, "end_header")?;
+
+    for (index, vertex) in mesh.vertexes.iter().enumerate() {
+        write!(writer, "{} {} {}", vertex.x, vertex.y, vertex.z)?;
+        if let Some(normals) = mesh.normals.as_ref() {
+            let normal = normals[index];
+            write!(writer, " {} {} {}", normal.x, normal.y, normal.z)?;
+        }
+        if let Some(uvs) = mesh.uvs.as_ref() {
+            write!(writer, " {} {}", uvs[index].x, uvs[index].y)?;
+        }
+        writeln!(writer)?;
+    }
+    for [a, b, c] in mesh.indices.iter() {
+        writeln!(writer, "3 {} {} {}", a, b, c)?;
+    }
+    writer.flush()
+}
+
+/// Write the mesh to an ASCII PLY file, see `write_ply`
+pub fn save_ply<P: AsRef<Path>>(path: P, mesh: &Mesh) -> IoResult<()> {
+    write_ply(BufWriter::new(File::create(path)?), mesh)
+}
+
+/// Read a mesh from an ASCII PLY file, see `read_ply`
+pub fn load_ply<P: AsRef<Path>>(path: P) -> IoResult<Mesh> {
+    read_ply(BufReader::new(File::open(path)?))
+}
@@ -1,58 +1,84 @@
-use glium::Rect;
+use std::time::{Duration, Instant};
 
-use crate::cpu_buffer::CPUBuffer;
+use crate::cpu_buffer::{CPUBuffer, Rect};
 
-use self::{camera::Camera, curve_triangle::CurveTriangle, curve_triangle::IntersectionError};
+use self::{
+    camera::Camera,
+    renderer::{draw_rect, RendererKind},
+    scene::Scene,
+    tiles::TileScheduler,
+};
 
 pub mod aabb;
 pub mod camera;
 pub mod common_raytracing;
 pub mod curve_raytracing;
 pub mod curve_triangle;
+pub mod fit;
+pub mod hit;
+pub mod light;
+pub mod mesh;
 pub mod trihedral_traycing;
 pub mod obb;
+pub mod path_raytracing;
+pub mod progressive;
 mod rasterisator;
 pub mod ray;
+pub mod renderer;
+pub mod root_solver;
+pub mod scene;
+pub mod shaded_raytracing;
+pub mod shading;
+pub mod tiles;
 mod triange_shell;
 pub mod triangle;
+pub mod whitted_raytracing;
 
-pub fn draw_to(cpu_buffer: &mut CPUBuffer, camera: &Camera, shape: &mut Vec<CurveTriangle>) {
-    let first_half = Rect {
-        left: 0,
-        bottom: 0,
-        width: cpu_buffer.width / 2,
-        height: cpu_buffer.height,
-    };
-    let second_half = Rect {
-        left: cpu_buffer.width / 2,
-        bottom: 0,
-        width: cpu_buffer.width / 2,
-        height: cpu_buffer.height,
-    };
-    // let second_half = Rect {
-    //     left: 0, bottom: 0,
-    //     width: cpu_buffer.width, height: cpu_buffer.height
-    // };
-    // rasterisator::draw_rect_for_triangles(cpu_buffer, &first_half, &shape, camera);
-    common_raytracing::draw_rect_for_triangulation(
-        cpu_buffer,
-        &first_half,
-        shape,
-        camera,
-        Some(false),
-    );
-    // curve_raytracing::draw_rect_for_curve_surface(
-    //     cpu_buffer,
-    //     &second_half,
-    //     shape,
-    //     camera,
-    //     Some(false),
-    // );
-    trihedral_traycing::draw_rect_for_triangulation(
-        cpu_buffer,
-        &second_half,
-        shape,
-        camera,
-        Some(false),
-    );
+/// Split the buffer into equal vertical strips, one per renderer, to compare them side by side.
+/// Camera ratio should be `strip width / height`. Returns the render time of every renderer.
+pub fn draw_to(
+    cpu_buffer: &mut CPUBuffer,
+    camera: &Camera,
+    scene: &Scene,
+    renderers: &[RendererKind],
+    scheduler: &TileScheduler,
+    samples_per_pixel: u32,
+    trace_depth: u32,
+) -> Vec<Duration> {
+    if renderers.is_empty() {
+        return Vec::new();
+    }
+    let strips = renderer_strips(cpu_buffer.width, cpu_buffer.height, renderers.len());
+
+    let mut times = Vec::with_capacity(renderers.len());
+    for (kind, strip) in renderers.iter().zip(strips.iter()) {
+        // rasterisator::draw_rect_for_triangles(cpu_buffer, &strip, &scene.shape[0], camera);
+
+        let start = Instant::now();
+        draw_rect(
+            cpu_buffer,
+            strip,
+            camera,
+            scene,
+            kind.renderer(trace_depth).as_ref(),
+            scheduler,
+            samples_per_pixel,
+        );
+        times.push(start.elapsed());
+    }
+    times
+}
+
+/// Equal vertical strips of the image, one per renderer, the last pixels of a row are left
+/// when the width doesn't divide
+pub fn renderer_strips(width: u32, height: u32, count: usize) -> Vec<Rect> {
+    let strip_width = width / count.max(1) as u32;
+    (0..count)
+        .map(|index| Rect {
+            left: strip_width * index as u32,
+            bottom: 0,
+            width: strip_width,
+            height,
+        })
+        .collect()
 }
@@ -1,10 +1,11 @@
-use cgmath::{ElementWise, Vector3};
+use cgmath::{ElementWise, Vector3, Zero};
 
-use crate::utils::VectorExt;
+use super::{
+    hit::{Hit, Intersect},
+    ray::Ray,
+};
 
-use super::ray::Ray;
-
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy)]
 pub struct AABBox {
     pub min_x: f32,
     pub max_x: f32,
@@ -31,26 +32,63 @@ impl AABBox {
         )
     }
 
-    pub fn get_slice_for_ray(&self, ray: &Ray) -> (f32, f32) {
-        // https://iquilezles.org/articles/intersectors/
-
-        let inv_dir = 1.0 / ray.direction;
-        let n = inv_dir.mul_element_wise(ray.origin - self.center());
-        let k = inv_dir.abs().mul_element_wise(self.half_size());
-        let t1 = -n - k;
-        let t2 = -n + k;
+    /// Part of the ray inside the box, `None` if the ray misses it
+    pub fn get_slice_for_ray(&self, ray: &Ray) -> Option<(f32, f32)> {
+        let (t1, t2) = self.slab_distances(ray);
 
         let t_n = t1.x.max(t1.y.max(t1.z));
         let t_f = t2.x.min(t2.y.min(t2.z));
 
         // no intersection
         if t_n > t_f || t_f < 0.0 {
-            return (-1.0, -1.0);
+            return None;
         }
 
-        // this is normal of side
-        // oN = -sign(rd)*step(t1.yzx,t1.xyz)*step(t1.zxy,t1.xyz);
+        Some((t_n.max(0.), t_f))
+    }
+
+    /// Distances to near and far planes of each slab
+    fn slab_distances(&self, ray: &Ray) -> (Vector3<f32>, Vector3<f32>) {
+        // per axis, so a ray parallel to a slab gets infinite distances of the same sign
+        // outside of it instead of NaN
+        let inv_dir = 1.0 / ray.direction;
+        let min = Vector3::new(self.min_x, self.min_y, self.min_z);
+        let max = Vector3::new(self.max_x, self.max_y, self.max_z);
+        let t_min = (min - ray.origin).mul_element_wise(inv_dir);
+        let t_max = (max - ray.origin).mul_element_wise(inv_dir);
+        (t_min.zip(t_max, f32::min), t_min.zip(t_max, f32::max))
+    }
+}
+
+impl Intersect for AABBox {
+    /// Entry point of the box, or the exit point for rays starting inside
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let (t1, t2) = self.slab_distances(ray);
+
+        let t_n = t1.x.max(t1.y.max(t1.z));
+        let t_f = t2.x.min(t2.y.min(t2.z));
+        if t_n > t_f || t_f <= 0.0 {
+            return None;
+        }
+
+        // normal of the side is the axis of the slab that bounds the slice
+        let (t, axis_distances, bound) = if t_n > 0. {
+            (t_n, t1, t_n)
+        } else {
+            (t_f, t2, t_f)
+        };
+        let mut outward_normal = Vector3::zero();
+        for axis in 0..3 {
+            if axis_distances[axis] == bound {
+                outward_normal[axis] = if t_n > 0. {
+                    -ray.direction[axis].signum()
+                } else {
+                    ray.direction[axis].signum()
+                };
+                break;
+            }
+        }
 
-        (t_n.max(0.), t_f)
+        Some(Hit::new(ray, t, outward_normal, Vector3::zero()))
     }
 }
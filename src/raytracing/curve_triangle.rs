@@ -3,11 +3,21 @@ use bvh::{
     bounding_hierarchy::BHShape,
 };
 use bvh::aabb::Aabb;
-use cgmath::{num_traits::Inv, Array, ElementWise, InnerSpace, Vector3, VectorSpace};
+use cgmath::{
+    num_traits::Inv, Array, BaseFloat, ElementWise, InnerSpace, Vector3, VectorSpace, Zero,
+};
 
 use crate::utils::{get_vectors_relation, MinMaxIterExt, VectorExt};
 
-use super::{aabb::AABBox, ray::Ray, triange_shell::TriangleShell, triangle::Triangle};
+use super::{
+    aabb::AABBox,
+    hit::{Hit, Intersect},
+    obb::Obb,
+    ray::Ray,
+    root_solver::{Bracket, Convergence, Root, RootIsolation, RootSolver},
+    triange_shell::TriangleShell,
+    triangle::Triangle,
+};
 
 const UNIT_VECTOR: Vector3<f32> = Vector3 {
     x: 1.,
@@ -15,6 +25,65 @@ const UNIT_VECTOR: Vector3<f32> = Vector3 {
     z: 1.,
 };
 
+/// Step in barycentric coords for surface derivatives
+const NORMAL_BARY_STEP: f64 = 1e-5;
+
+/// Barycentric coords of the base vertexes
+pub const BASE_BARY: [Vector3<f32>; 3] = [
+    Vector3 {
+        x: 1.,
+        y: 0.,
+        z: 0.,
+    },
+    Vector3 {
+        x: 0.,
+        y: 1.,
+        z: 0.,
+    },
+    Vector3 {
+        x: 0.,
+        y: 0.,
+        z: 1.,
+    },
+];
+
+/// Edges whose normals are closer to parallel are straight, relative to the edge length
+const EDGE_PIVOT_MIN_DET: f32 = 1e-4;
+
+/// Edges with pivots farther than this many edge lengths are straight, the sag is below 1%
+const EDGE_PIVOT_MAX_DISTANCE: f32 = 16.;
+
+/// Part of the tolerance of the adaptive triangulation for the chordal error of the edges.
+/// Inside a triangle the surface deviates up to 4/3 of the largest error of its edges
+/// (exactly for quadratic surfaces), so triangles stay within the tolerance.
+pub const EDGE_TOLERANCE_PART: f32 = 0.75;
+
+/// Grid subdivisions of the base edges for the surface bounds
+const BOUNDS_SAMPLES: usize = 32;
+
+/// Barycentric coords this much outside of the base still belong to the patch
+const BARY_EDGE_TOLERANCE: f32 = 1e-5;
+
+/// Directions along the base edges in barycentric coords: v0->v1, v1->v2, v2->v0
+const BARY_EDGE_DIRECTIONS: [Vector3<f32>; 3] = [
+    Vector3 {
+        x: -1.,
+        y: 1.,
+        z: 0.,
+    },
+    Vector3 {
+        x: 0.,
+        y: -1.,
+        z: 1.,
+    },
+    Vector3 {
+        x: 1.,
+        y: 0.,
+        z: -1.,
+    },
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum IntersectionError {
     BehindRay,
     CantSubrayBase,
@@ -37,6 +106,14 @@ enum ISS {
     Outside,
 }
 
+/// Result of `CurveTriangle::triangulate_adaptive`
+#[derive(Debug, Clone, Copy)]
+pub struct TriangulationReport {
+    pub triangles: usize,
+    /// Largest distance to the surface measured in edge midpoints and centers of the triangles
+    pub max_error: f32,
+}
+
 pub struct CurveTriangle {
     pub base: Triangle,
     pub pivots: [Vector3<f32>; 3],
@@ -48,6 +125,12 @@ pub struct CurveTriangle {
     pub triangulation: Vec<Triangle>,
 
     pub tr_shell: Option<TriangleShell<4>>,
+    /// Culling volume checked before the shell, see `precalc_obb`
+    pub obb: Option<Obb>,
+    /// Conservative box of the surface, see `precalc_bounds`
+    pub bounds: Option<AABBox>,
+    /// How the surface is searched along the ray
+    pub solver: RootSolver,
 
     bhv_node_index: usize,
 }
@@ -77,13 +160,33 @@ impl CurveTriangle {
             opposite_root,
             triangulation: Vec::new(),
             tr_shell: None,
+            obb: None,
+            bounds: None,
+            solver: RootSolver::default(),
             bhv_node_index: 0,
         };
         ct.precalc_triangle_shell();
+        ct.precalc_bounds();
         ct
     }
 
+    /// Patch through the vertexes of the triangle tangent to the planes of the vertex normals,
+    /// pivots and koefs of the edges are given by `edge_pivot`
+    pub fn from_vertex_normals(triangle: Triangle, normals: [Vector3<f32>; 3]) -> CurveTriangle {
+        let vertexes = triangle.vertexes;
+        let mut pivots = [Vector3::zero(); 3];
+        let mut curve_koefs = [0.; 3];
+        for edge in 0..3 {
+            let next = (edge + 1) % 3;
+            (pivots[edge], curve_koefs[edge]) =
+                edge_pivot(vertexes[edge], normals[edge], vertexes[next], normals[next]);
+        }
+        CurveTriangle::new(triangle, pivots, curve_koefs)
+    }
+
+    /// Uniform grid of `accuracy^2` triangles, replaces the previous triangulation
     pub fn triangulate(&mut self, accuracy: i32) {
+        self.triangulation.clear();
         let mut line_size = 1;
         let mut previous_points = vec![self.base.vertexes[1]];
 
@@ -121,18 +224,346 @@ impl CurveTriangle {
         }
     }
 
-    pub fn intersect(&self, ray: &Ray) -> Result<(f32, Vector3<f32>), IntersectionError> {
-        let (mut t_start, mut t_end) = self.tr_shell.as_ref().unwrap().get_slice_for_ray(ray);
-        if t_start < 0. {
+    /// Split edges of the base in barycentric coords while the surface deviates from
+    /// the chord more than `EDGE_TOLERANCE_PART` of `tolerance`, at most `max_depth` times
+    /// per base edge, so the triangles deviate from the surface at most `tolerance` where it is
+    /// close to quadratic at their size. Limited by `max_depth` near sharp features.
+    /// Decision to split an edge depends only on the edge, so neighbour triangles
+    /// split shared edges the same way and the triangulation has no cracks.
+    /// Replaces the previous triangulation.
+    pub fn triangulate_adaptive(&mut self, tolerance: f32, max_depth: u32) -> TriangulationReport {
+        let min_edge = 0.5f32.powi(max_depth as i32);
+        let edge_tolerance = tolerance * EDGE_TOLERANCE_PART;
+        let mut bary_triangles = Vec::new();
+        self.subdivide_bary(
+            BASE_BARY,
+            max_depth,
+            &|a, b| bary_edge_length(a, b) > min_edge && self.edge_error(a, b) > edge_tolerance,
+            &mut bary_triangles,
+        );
+
+        let mut max_error: f32 = 0.;
+        self.triangulation = bary_triangles
+            .iter()
+            .map(|&corners| {
+                max_error = max_error.max(self.bary_triangle_error(corners));
+                Triangle::new(corners.map(|bary| self.get_surface_point_by_bary(bary)))
+            })
+            .collect();
+
+        TriangulationReport {
+            triangles: self.triangulation.len(),
+            max_error,
+        }
+    }
+
+    /// Split the triangle in barycentric coords by the midpoints of edges marked
+    /// by `needs_split`, the winding of `corners` is kept. `needs_split` must depend
+    /// only on the edge and stop splitting edges shorter than `0.5^max_depth`.
+    pub fn subdivide_bary<F>(
+        &self,
+        corners: [Vector3<f32>; 3],
+        max_depth: u32,
+        needs_split: &F,
+        triangles: &mut Vec<[Vector3<f32>; 3]>,
+    ) where
+        F: Fn(Vector3<f32>, Vector3<f32>) -> bool,
+    {
+        // bisections of the inner edges need a few more levels than the base edges
+        self.subdivide_bary_step(corners, 2 * max_depth + 2, needs_split, triangles);
+    }
+
+    fn subdivide_bary_step<F>(
+        &self,
+        corners: [Vector3<f32>; 3],
+        depth_left: u32,
+        needs_split: &F,
+        triangles: &mut Vec<[Vector3<f32>; 3]>,
+    ) where
+        F: Fn(Vector3<f32>, Vector3<f32>) -> bool,
+    {
+        let split = [
+            needs_split(corners[0], corners[1]),
+            needs_split(corners[1], corners[2]),
+            needs_split(corners[2], corners[0]),
+        ];
+        let splits = split.iter().filter(|&&split| split).count();
+
+        if splits == 0 || depth_left == 0 {
+            triangles.push(corners);
+            return;
+        }
+
+        let mut recurse = |corners: [Vector3<f32>; 3]| {
+            self.subdivide_bary_step(corners, depth_left - 1, needs_split, triangles)
+        };
+        let middle = |a: Vector3<f32>, b: Vector3<f32>| (a + b) * 0.5;
+
+        match splits {
+            3 => {
+                let m0 = middle(corners[0], corners[1]);
+                let m1 = middle(corners[1], corners[2]);
+                let m2 = middle(corners[2], corners[0]);
+                recurse([corners[0], m0, m2]);
+                recurse([m0, corners[1], m1]);
+                recurse([m2, m1, corners[2]]);
+                recurse([m0, m1, m2]);
+            }
+            1 => {
+                // rotate the split edge to c0-c1, keeping the winding
+                let first = split.iter().position(|&split| split).unwrap();
+                let [c0, c1, c2] = [0, 1, 2].map(|i| corners[(first + i) % 3]);
+                let m0 = middle(c0, c1);
+                recurse([c0, m0, c2]);
+                recurse([m0, c1, c2]);
+            }
+            _ => {
+                // rotate the whole edge to c2-c0
+                let whole = split.iter().position(|&split| !split).unwrap();
+                let [c0, c1, c2] = [1, 2, 3].map(|i| corners[(whole + i) % 3]);
+                let (m0, m1) = (middle(c0, c1), middle(c1, c2));
+                recurse([m0, c1, m1]);
+                // split the rest quad by the shorter diagonal
+                let distance = |a: Vector3<f32>, b: Vector3<f32>| {
+                    (self.get_surface_point_by_bary(a) - self.get_surface_point_by_bary(b)).magnitude()
+                };
+                if distance(c0, m1) <= distance(m0, c2) {
+                    recurse([c0, m0, m1]);
+                    recurse([c0, m1, c2]);
+                } else {
+                    recurse([m0, m1, c2]);
+                    recurse([c0, m0, c2]);
+                }
+            }
+        }
+    }
+
+    /// Distance from the middle of the chord to the surface
+    pub fn edge_error(&self, a: Vector3<f32>, b: Vector3<f32>) -> f32 {
+        let chord_middle = (self.get_surface_point_by_bary(a) + self.get_surface_point_by_bary(b)) * 0.5;
+        (self.get_surface_point_by_bary((a + b) * 0.5) - chord_middle).magnitude()
+    }
+
+    /// Largest distance to the surface of the flat triangle in the edge midpoints and the center
+    pub fn bary_triangle_error(&self, corners: [Vector3<f32>; 3]) -> f32 {
+        let points = corners.map(|bary| self.get_surface_point_by_bary(bary));
+        let center = (corners[0] + corners[1] + corners[2]) / 3.;
+        let center_error = (self.get_surface_point_by_bary(center)
+            - (points[0] + points[1] + points[2]) / 3.)
+            .magnitude();
+        center_error
+            .max(self.edge_error(corners[0], corners[1]))
+            .max(self.edge_error(corners[1], corners[2]))
+            .max(self.edge_error(corners[2], corners[0]))
+    }
+
+    /// Intersection with the curve surface, error tells why the ray is missed
+    pub fn try_intersect(&self, ray: &Ray) -> Result<Hit, IntersectionError> {
+        let root = self.solve(ray)?;
+        let bary = self.bary_at(ray, root.t)?;
+        Ok(Hit::new(ray, root.t, self.hit_normal_at_bary(bary), bary))
+    }
+
+    /// Every intersection with the curve surface sorted along the ray.
+    /// Roots are isolated by `self.solver.isolation` or by the default isolation
+    /// when the solver brackets a single root.
+    pub fn intersect_all(&self, ray: &Ray) -> Vec<Hit> {
+        if self.is_flat() {
+            return self.try_intersect(ray).into_iter().collect();
+        }
+        let slice = match self.ray_slice(ray) {
+            Ok(slice) => slice,
+            Err(_) => return Vec::new(),
+        };
+        let isolation = self.solver.isolation.unwrap_or_default();
+
+        self.isolated_roots(ray, &isolation, slice)
+            .filter_map(|root| {
+                let bary = self.bary_at(ray, root.t).ok()?;
+                Some(Hit::new(ray, root.t, self.hit_normal_at_bary(bary), bary))
+            })
+            .collect()
+    }
+
+    /// Distance to the surface along the ray with the convergence status of `self.solver`
+    pub fn solve(&self, ray: &Ray) -> Result<Root, IntersectionError> {
+        if self.is_flat() {
+            return self.solve_flat(ray);
+        }
+        let slice = self.ray_slice(ray)?;
+        match self.solver.isolation {
+            Some(isolation) => {
+                // nearest root which projects inside the base
+                let mut error = IntersectionError::NoIntersections;
+                for root in self.isolated_roots(ray, &isolation, slice) {
+                    if self.bary_at(ray, root.t).is_ok() {
+                        return Ok(root);
+                    }
+                    error = IntersectionError::CantSubrayBase;
+                }
+                Err(error)
+            }
+            None => self.solve_bracketed(ray, slice),
+        }
+    }
+
+    /// Straight edges make the surface the base triangle, only parametrized differently,
+    /// so the distance to it along the cone of the root point is not defined
+    pub fn is_flat(&self) -> bool {
+        self.curve_koefs.iter().all(|&koef| koef == 1.)
+    }
+
+    fn solve_flat(&self, ray: &Ray) -> Result<Root, IntersectionError> {
+        let bary = match self.base.intersect_with_bary(ray) {
+            Ok((_, bary)) => bary,
+            Err(bary) => bary,
+        };
+        // rays on a shared edge must hit either side of it
+        let nearest_edge = bary.x.min(bary.y).min(bary.z);
+        if nearest_edge.is_nan() || nearest_edge < -BARY_EDGE_TOLERANCE {
+            return Err(IntersectionError::NoIntersections);
+        }
+        let normal = self.base.normal();
+        let t = (self.base.vertexes[0] - ray.origin).dot(normal) / ray.direction.dot(normal);
+        if t.is_nan() || t <= 0. {
             return Err(IntersectionError::BehindRay);
         }
+        Ok(Root {
+            t,
+            iterations: 0,
+            convergence: Convergence::Converged,
+        })
+    }
+
+    /// Part of the ray inside the shell with values of `intersect_step` on its ends
+    fn ray_slice(&self, ray: &Ray) -> Result<(f32, f32, f32, f32), IntersectionError> {
+        if let Some(obb) = self.obb.as_ref() {
+            if obb.get_slice_for_ray(ray).is_none() {
+                return Err(IntersectionError::BehindRay);
+            }
+        }
+        let (mut t_start, mut t_end) = match self.tr_shell.as_ref().unwrap().get_slice_for_ray(ray) {
+            Some(slice) => slice,
+            None => return Err(IntersectionError::BehindRay),
+        };
 
-        // first step
         let mut start_sdf = self.intersect_step(t_start, ray);
         let mut end_sdf = self.intersect_step(t_end, ray);
+        // the step is undefined in the root point, it is the apex of the shell
+        // and NaN would pass for a sign change
+        if start_sdf.is_nan() {
+            t_start += (t_end - t_start) * 1e-4;
+            start_sdf = self.intersect_step(t_start, ray);
+        }
+        if end_sdf.is_nan() {
+            t_end -= (t_end - t_start) * 1e-4;
+            end_sdf = self.intersect_step(t_end, ray);
+        }
+        Ok((t_start, start_sdf, t_end, end_sdf))
+    }
+
+    /// Refined roots of every sign change found by the isolation, sorted along the ray
+    fn isolated_roots<'a>(
+        &'a self,
+        ray: &'a Ray,
+        isolation: &RootIsolation,
+        slice: (f32, f32, f32, f32),
+    ) -> impl Iterator<Item = Root> + 'a {
+        let step = move |t| self.intersect_step(t, ray);
+        self.isolated_brackets(ray, isolation, slice)
+            .into_iter()
+            .map(move |bracket| {
+                self.solver.refine(
+                    step,
+                    bracket.t_start,
+                    bracket.f_start,
+                    bracket.t_end,
+                    bracket.f_end,
+                )
+            })
+    }
+
+    /// Every sign change of `intersect_step` in the slice, sorted along the ray.
+    /// The distance to the root point, the first term of the step, changes at most as fast
+    /// as the ray moves, so the length of the direction is the least slope bound of the isolation.
+    fn isolated_brackets(
+        &self,
+        ray: &Ray,
+        isolation: &RootIsolation,
+        (t_start, f_start, t_end, f_end): (f32, f32, f32, f32),
+    ) -> Vec<Bracket> {
+        let ends = Bracket {
+            t_start,
+            f_start,
+            t_end,
+            f_end,
+        };
+        isolation.brackets(
+            |t| self.intersect_step(t, ray),
+            ends,
+            ray.direction.magnitude(),
+        )
+    }
+
+    /// Whether the surface crosses the ray before `t_max`, like `solve` without refining the root:
+    /// a sign change of the bracket is enough. Shadow rays need no more.
+    pub fn occludes(&self, ray: &Ray, t_max: f32) -> bool {
+        if self.is_flat() {
+            return self.solve_flat(ray).is_ok_and(|root| root.t < t_max);
+        }
+        let (t_start, start_sdf, mut t_end, mut end_sdf) = match self.ray_slice(ray) {
+            Ok(slice) => slice,
+            Err(_) => return false,
+        };
+        if t_start >= t_max {
+            return false;
+        }
+        if t_end > t_max {
+            t_end = t_max;
+            end_sdf = self.intersect_step(t_end, ray);
+        }
+        let slice = (t_start, start_sdf, t_end, end_sdf);
+
+        // the bracket must be over the base, like the root in `solve`
+        let over_base = |bracket: &Bracket| {
+            self.bary_at(ray, (bracket.t_start + bracket.t_end) * 0.5).is_ok()
+        };
+        match self.solver.isolation {
+            Some(isolation) => self
+                .isolated_brackets(ray, &isolation, slice)
+                .iter()
+                .any(over_base),
+            None => self.bracket(ray, slice).as_ref().is_some_and(over_base),
+        }
+    }
+
+    /// Bisection steps keep a single half of the slice, so only one of several roots is found
+    fn solve_bracketed(
+        &self,
+        ray: &Ray,
+        slice: (f32, f32, f32, f32),
+    ) -> Result<Root, IntersectionError> {
+        let bracket = self
+            .bracket(ray, slice)
+            .ok_or(IntersectionError::NoIntersections)?;
+        Ok(self.solver.refine(
+            |t| self.intersect_step(t, ray),
+            bracket.t_start,
+            bracket.f_start,
+            bracket.t_end,
+            bracket.f_end,
+        ))
+    }
+
+    /// `self.solver.bracket_steps` bisection steps looking for a sign change
+    fn bracket(
+        &self,
+        ray: &Ray,
+        (mut t_start, mut start_sdf, mut t_end, mut end_sdf): (f32, f32, f32, f32),
+    ) -> Option<Bracket> {
         let mut is_intersected = false;
         // check intersection
-        for _ in 0..5 {
+        for _ in 0..self.solver.bracket_steps {
             let t_middle = (t_start + t_end) * 0.5;
             let middle_sdf = self.intersect_step(t_middle, ray);
 
@@ -156,7 +587,7 @@ impl CurveTriangle {
         }
 
         if !is_intersected {
-            return Err(IntersectionError::NoIntersections);
+            return None;
             // return match (prev_state, state) {
             //     (ISS::Undefined, ISS::Undefined) => Err(IntersectionError::UndefUndef),
             //     (ISS::Undefined, ISS::Outside) => Err(IntersectionError::UndefOut),
@@ -170,38 +601,35 @@ impl CurveTriangle {
             // }
         }
 
-        // get intersection
-        for _ in 0..3 {
-            let t_middle = (t_start + t_end) * 0.5;
-            let middle_sdf = self.intersect_step(t_middle, ray);
-
-            if start_sdf.signum() != middle_sdf.signum() {
-                t_end = t_middle;
-            } else {
-                t_start = t_middle;
-                start_sdf = middle_sdf;
-            }
-        }
-        let t_middle = (t_start + t_end) * 0.5;
-        let point = ray.get_point(t_middle);
+        Some(Bracket {
+            t_start,
+            f_start: start_sdf,
+            t_end,
+            f_end: end_sdf,
+        })
+    }
 
-        // println!("h: {:?}", debug_state_history);
+    /// Barycentric coords of the surface point on the ray, the point must project inside the base
+    fn bary_at(&self, ray: &Ray, t: f32) -> Result<Vector3<f32>, IntersectionError> {
+        self.project_to_base(ray.get_point(t))
+            .ok_or(IntersectionError::CantSubrayBase)
+    }
 
-        return match self.base.intersect(&Ray {
+    /// Barycentric coords of the point projected to the base from `root_point`,
+    /// the way the surface is parametrized. `None` if the projection misses the base.
+    pub fn project_to_base(&self, point: Vector3<f32>) -> Option<Vector3<f32>> {
+        match self.base.intersect_with_bary(&Ray {
             origin: point,
             direction: (self.root_point - point).normalize(),
         }) {
-            Ok((_, barri)) => Ok((t_middle, barri)),
-            Err(_) => {
-                // println!("bad barri   t: {:.2} t_start: {:.2} t_end: {:.2} t_step: {:.2}   point {:?}   on cone {:?}  h: {:?}",
-                //          t, t_start, t_end, t_step, point, point_on_cone_pivot, debug_state_history);
-                Err(IntersectionError::CantSubrayBase)
+            Ok((_, barri)) => Some(barri),
+            // roots on a shared edge are rounded to either side of it
+            Err(barri) if barri.x.min(barri.y).min(barri.z) >= -BARY_EDGE_TOLERANCE => {
+                let barri = barri.map(|w| w.max(0.));
+                Some(barri / barri.sum())
             }
-        };
-        // println!("end   t_start: {:.2} t_end: {:.2} t_step: {:.2}     h: {:?}",
-        //             t_start, t_end, t_step, debug_state_history);
-
-        //
+            Err(_) => None,
+        }
     }
 
     #[inline]
@@ -216,7 +644,7 @@ impl CurveTriangle {
             direction: (self.root_point - point_on_ray).normalize(),
         };
 
-        let trusted_intersection = self.base.intersect(&sub_ray);
+        let trusted_intersection = self.base.intersect_with_bary(&sub_ray);
         let bary = match trusted_intersection {
             Ok((_, res)) => res,
             Err(res) => res,
@@ -246,6 +674,86 @@ impl CurveTriangle {
         cone_dfs
     }
 
+    /// Unit normal of the surface, oriented away from `root_point`.
+    /// Computed by finite differences along the base edges in double precision,
+    /// one-sided near the borders, so neighbour patches with a shared curve
+    /// get the same normals on the shared edge.
+    pub fn normal_at_bary(&self, bary: Vector3<f32>) -> Vector3<f32> {
+        let vertexes = self.base.vertexes.map(|v| v.cast::<f64>().unwrap());
+        let pivots = self.pivots.map(|v| v.cast::<f64>().unwrap());
+        let curve_koefs = self.curve_koefs.map(|v| v as f64);
+        self.surface_normal(bary, |bary| {
+            CurveTriangle::surface_point_by_bary(&vertexes, &pivots, &curve_koefs, bary)
+        })
+    }
+
+    /// Unit normal at the hit with `bary` of `project_to_base`, oriented away from `root_point`.
+    /// Rays hit the point on the line from `root_point` through the base point, at the distance
+    /// of the surface point with the same `bary` (see `intersect_step`), not the surface point.
+    /// Flat patches are hit on the base plane (see `solve_flat`), their normal is the base normal.
+    pub fn hit_normal_at_bary(&self, bary: Vector3<f32>) -> Vector3<f32> {
+        if self.is_flat() {
+            let normal = self.base.normal();
+            return if normal.dot(self.base.vertexes[0] - self.root_point) < 0. {
+                -normal
+            } else {
+                normal
+            };
+        }
+        let vertexes = self.base.vertexes.map(|v| v.cast::<f64>().unwrap());
+        let pivots = self.pivots.map(|v| v.cast::<f64>().unwrap());
+        let curve_koefs = self.curve_koefs.map(|v| v as f64);
+        let root_point = self.root_point.cast::<f64>().unwrap();
+        self.surface_normal(bary, |bary| {
+            let base_point = vertexes[0] * bary.x + vertexes[1] * bary.y + vertexes[2] * bary.z;
+            let surface_point =
+                CurveTriangle::surface_point_by_bary(&vertexes, &pivots, &curve_koefs, bary);
+            let distance = (surface_point - root_point).magnitude();
+            root_point + (base_point - root_point).normalize() * distance
+        })
+    }
+
+    /// Normal of the surface given by barycentric coords, see `normal_at_bary`
+    fn surface_normal<F>(&self, bary: Vector3<f32>, surface: F) -> Vector3<f32>
+    where
+        F: Fn(Vector3<f64>) -> Vector3<f64>,
+    {
+        let bary = bary.map(|v| v.max(0.) as f64);
+        let bary = bary / bary.sum();
+
+        let tangents: Vec<Vector3<f64>> = BARY_EDGE_DIRECTIONS
+            .iter()
+            .filter_map(|&direction| {
+                surface_derivative(&surface, bary, direction.cast::<f64>().unwrap())
+            })
+            .collect();
+
+        // take the best conditioned pair of tangents
+        let mut normal = Vector3::zero();
+        for (index, first) in tangents.iter().enumerate() {
+            for second in tangents[index + 1..].iter() {
+                let candidate = first.cross(*second);
+                if candidate.magnitude2() > normal.magnitude2() {
+                    normal = candidate;
+                }
+            }
+        }
+        // NaN candidates never compare greater, so the normal is zero or finite
+        if normal.magnitude2() <= 0. {
+            let vertexes = self.base.vertexes.map(|v| v.cast::<f64>().unwrap());
+            normal = (vertexes[1] - vertexes[0]).cross(vertexes[2] - vertexes[0]);
+        }
+        let normal = normal.normalize();
+
+        let root_point = self.root_point.cast::<f64>().unwrap();
+        let normal = if normal.dot(surface(bary) - root_point) < 0. {
+            -normal
+        } else {
+            normal
+        };
+        normal.cast::<f32>().unwrap()
+    }
+
     pub fn precalc_triangle_shell(&mut self) {
         self.tr_shell = Some(TriangleShell {
             triangles: [
@@ -261,6 +769,57 @@ impl CurveTriangle {
         });
     }
 
+    /// Box of the surface sampled on a dense grid, grown by half of the largest
+    /// distance between neighbour samples, so the surface between samples stays inside.
+    pub fn precalc_bounds(&mut self) {
+        let samples = BOUNDS_SAMPLES;
+        let mut rows: Vec<Vec<Vector3<f32>>> = Vec::with_capacity(samples + 1);
+        for i in 0..=samples {
+            let row = (0..=samples - i)
+                .map(|j| {
+                    let (w1, w2) = (i as f32 / samples as f32, j as f32 / samples as f32);
+                    self.get_surface_point_by_bary(Vector3::new((1. - w1 - w2).max(0.), w1, w2))
+                })
+                .collect();
+            rows.push(row);
+        }
+
+        let mut margin: f32 = 0.;
+        for i in 0..=samples {
+            for j in 0..=samples - i {
+                let point = rows[i][j];
+                if j < samples - i {
+                    margin = margin.max((rows[i][j + 1] - point).magnitude());
+                }
+                if i < samples && j < samples - i {
+                    margin = margin.max((rows[i + 1][j] - point).magnitude());
+                }
+            }
+        }
+        margin *= 0.5;
+
+        let bound = |axis: usize| {
+            let (min, max) = rows.iter().flatten().map(|v| v[axis]).min_max();
+            (min - margin, max + margin)
+        };
+        let ((min_x, max_x), (min_y, max_y), (min_z, max_z)) = (bound(0), bound(1), bound(2));
+
+        self.bounds = Some(AABBox {
+            min_x,
+            max_x,
+            min_y,
+            max_y,
+            min_z,
+            max_z,
+        });
+    }
+
+    /// Cull rays by an oriented box around the shell, cheaper than the shell test
+    /// and tighter than the axis aligned box for tilted bases
+    pub fn precalc_obb(&mut self) {
+        self.obb = Some(Obb::from_curve_triangle(self));
+    }
+
     #[inline]
     pub fn get_surface_point(&self, point_on_base: Vector3<f32>) -> Vector3<f32> {
         self.get_surface_point_by_bary(self.base.get_bary(point_on_base))
@@ -268,47 +827,65 @@ impl CurveTriangle {
 
     #[inline]
     pub fn get_surface_point_by_bary(&self, bary_of_point: Vector3<f32>) -> Vector3<f32> {
+        CurveTriangle::surface_point_by_bary(
+            &self.base.vertexes,
+            &self.pivots,
+            &self.curve_koefs,
+            bary_of_point,
+        )
+    }
+
+    /// Same as `get_surface_point_by_bary` for any float precision
+    #[inline]
+    pub(crate) fn surface_point_by_bary<S: BaseFloat>(
+        vertexes: &[Vector3<S>; 3],
+        pivots: &[Vector3<S>; 3],
+        curve_koefs: &[S; 3],
+        bary_of_point: Vector3<S>,
+    ) -> Vector3<S> {
+        let unit_vector = Vector3::from_value(S::one());
         let pair_relations = bary_of_point
-            .div_element_wise(UNIT_VECTOR - bary_of_point.zxy())
-            .map(|v| if v.is_nan() { 1. } else { v });
+            .div_element_wise(unit_vector - bary_of_point.zxy())
+            .map(|v| if v.is_nan() { S::one() } else { v });
 
         // get interpolation koefs
         let koefs = bary_of_point.mul_element_wise(pair_relations.yzx())
             + bary_of_point
                 .yzx()
-                .mul_element_wise(UNIT_VECTOR - pair_relations.zxy());
+                .mul_element_wise(unit_vector - pair_relations.zxy());
 
         // get points on curves
         let c0 = CurveTriangle::curve(
-            1. - pair_relations[0],
-            self.base.vertexes[0],
-            self.base.vertexes[1],
-            self.pivots[0],
-            self.curve_koefs[0],
+            S::one() - pair_relations[0],
+            vertexes[0],
+            vertexes[1],
+            pivots[0],
+            curve_koefs[0],
         );
         let c1 = CurveTriangle::curve(
-            1. - pair_relations[1],
-            self.base.vertexes[1],
-            self.base.vertexes[2],
-            self.pivots[1],
-            self.curve_koefs[1],
+            S::one() - pair_relations[1],
+            vertexes[1],
+            vertexes[2],
+            pivots[1],
+            curve_koefs[1],
         );
         let c2 = CurveTriangle::curve(
-            1. - pair_relations[2],
-            self.base.vertexes[2],
-            self.base.vertexes[0],
-            self.pivots[2],
-            self.curve_koefs[2],
+            S::one() - pair_relations[2],
+            vertexes[2],
+            vertexes[0],
+            pivots[2],
+            curve_koefs[2],
         );
 
-        let balanced_coef = koefs[0] * self.curve_koefs[0]
-            + koefs[1] * self.curve_koefs[1]
-            + koefs[2] * self.curve_koefs[2];
+        let balanced_coef =
+            koefs[0] * curve_koefs[0] + koefs[1] * curve_koefs[1] + koefs[2] * curve_koefs[2];
 
-        (c0.upowf(balanced_coef) * koefs[0]
-            + c1.upowf(balanced_coef) * koefs[1]
-            + c2.upowf(balanced_coef) * koefs[2])
-            .upowf(balanced_coef.inv())
+        upowf(
+            upowf(c0, balanced_coef) * koefs[0]
+                + upowf(c1, balanced_coef) * koefs[1]
+                + upowf(c2, balanced_coef) * koefs[2],
+            S::one() / balanced_coef,
+        )
     }
 
     #[inline]
@@ -351,19 +928,19 @@ impl CurveTriangle {
             .upowf(balanced_coef.inv())
     }
 
-    pub fn curve(
-        t: f32,
-        v1: Vector3<f32>,
-        v2: Vector3<f32>,
-        p: Vector3<f32>,
-        curve_koef: f32,
-    ) -> Vector3<f32> {
-        let fix_t = t.min(1.).max(0.);
-        let s = (1. - fix_t).powf(curve_koef);
+    pub fn curve<S: BaseFloat>(
+        t: S,
+        v1: Vector3<S>,
+        v2: Vector3<S>,
+        p: Vector3<S>,
+        curve_koef: S,
+    ) -> Vector3<S> {
+        let fix_t = t.min(S::one()).max(S::zero());
+        let s = (S::one() - fix_t).powf(curve_koef);
         let f = fix_t.powf(curve_koef);
-        let pow1 = (s / (s + f)).powf(curve_koef.inv());
-        let pow2 = (f / (s + f)).powf(curve_koef.inv());
-        pow1 * v1 + pow2 * v2 + (1. - pow1 - pow2) * p
+        let pow1 = (s / (s + f)).powf(S::one() / curve_koef);
+        let pow2 = (f / (s + f)).powf(S::one() / curve_koef);
+        v1 * pow1 + v2 * pow2 + p * (S::one() - pow1 - pow2)
     }
 
     pub fn curve_sqrt(t: f32, v1: Vector3<f32>, v2: Vector3<f32>, p: Vector3<f32>) -> Vector3<f32> {
@@ -376,34 +953,103 @@ impl CurveTriangle {
     }
 }
 
-impl Bounded<f32, 3> for CurveTriangle {
-    fn aabb(&self) -> Aabb<f32, 3> {
-        let (min_x, max_x) = self
-            .base
-            .vertexes
-            .iter()
-            .chain(&[self.opposite_root])
-            .map(|&v| v[0])
-            .min_max();
-        let (min_y, max_y) = self
-            .base
-            .vertexes
-            .iter()
-            .chain(&[self.opposite_root])
-            .map(|&v| v[1])
-            .min_max();
-        let (min_z, max_z) = self
-            .base
-            .vertexes
-            .iter()
-            .chain(&[self.opposite_root])
-            .map(|&v| v[2])
-            .min_max();
+/// Pivot and curve koef of the edge curve from `v1` to `v2` tangent to the planes with normals `n1`, `n2`.
+/// The curve with koef 2 leaves `v1` along `v2 - p` and comes to `v2` along `v1 - p`,
+/// so the pivot solves `(v2 - p)·n1 = 0`, `(v1 - p)·n2 = 0` in the plane of the edge and the mean normal.
+/// Edges without a sane solution are straight: koef 1 and the pivot behind the middle of the edge,
+/// where it only shapes the shell.
+pub fn edge_pivot(
+    v1: Vector3<f32>,
+    n1: Vector3<f32>,
+    v2: Vector3<f32>,
+    n2: Vector3<f32>,
+) -> (Vector3<f32>, f32) {
+    let edge = v2 - v1;
+    let length = edge.magnitude();
+    let mean_normal = if (n1 + n2).magnitude2() > 0. {
+        (n1 + n2).normalize()
+    } else {
+        n1
+    };
+    let middle = (v1 + v2) * 0.5;
+    let straight = (middle - mean_normal * (length * 0.5), 1.);
+
+    // p = v1 + a * edge + b * mean_normal
+    let det = edge.dot(n1) * mean_normal.dot(n2) - mean_normal.dot(n1) * edge.dot(n2);
+    if det.is_nan() || det.abs() <= EDGE_PIVOT_MIN_DET * length {
+        return straight;
+    }
+    let a = edge.dot(n1) * mean_normal.dot(n2) / det;
+    let b = -edge.dot(n1) * edge.dot(n2) / det;
+    let pivot = v1 + edge * a + mean_normal * b;
+
+    if (pivot - middle).magnitude() > EDGE_PIVOT_MAX_DISTANCE * length {
+        return straight;
+    }
+    (pivot, 2.)
+}
+
+/// Length of the edge in barycentric coords, 1 for the base edges
+pub fn bary_edge_length(a: Vector3<f32>, b: Vector3<f32>) -> f32 {
+    let length = (a - b).map(f32::abs);
+    length.x.max(length.y).max(length.z)
+}
+
+/// Derivative of the surface along the direction in barycentric coords,
+/// `None` if the base triangle has no room for a step in both ways
+fn surface_derivative<F>(
+    surface: &F,
+    bary: Vector3<f64>,
+    direction: Vector3<f64>,
+) -> Option<Vector3<f64>>
+where
+    F: Fn(Vector3<f64>) -> Vector3<f64>,
+{
+    let h = NORMAL_BARY_STEP;
+    let is_inside = |b: Vector3<f64>| b.x >= 0. && b.y >= 0. && b.z >= 0.;
+    let point = |shift: f64| surface(bary + direction * shift);
+
+    let one_sided = |sign: f64| {
+        if is_inside(bary + direction * (2. * sign * h)) {
+            // second order one-sided differences
+            (point(sign * h) * 4. - point(0.) * 3. - point(2. * sign * h)) / (2. * sign * h)
+        } else {
+            (point(sign * h) - point(0.)) / (sign * h)
+        }
+    };
+
+    match (is_inside(bary + direction * h), is_inside(bary - direction * h)) {
+        (true, true) => Some((point(h) - point(-h)) / (2. * h)),
+        (true, false) => Some(one_sided(1.)),
+        (false, true) => Some(one_sided(-1.)),
+        (false, false) => None,
+    }
+}
 
-        // get bounds for surface by binary search and ray casting
+/// Signed power, keeps sign of every component like `VectorExt::upowf`
+#[inline]
+fn upowf<S: BaseFloat>(vector: Vector3<S>, x: S) -> Vector3<S> {
+    vector.map(|v| {
+        let power = v.abs().powf(x);
+        if v.is_sign_negative() {
+            -power
+        } else {
+            power
+        }
+    })
+}
 
-        let min_p = nalgebra::Point3::new(min_x, min_y, min_z);
-        let max_p = nalgebra::Point3::new(max_x, max_y, max_z);
+impl Intersect for CurveTriangle {
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        self.try_intersect(ray).ok()
+    }
+}
+
+impl Bounded<f32, 3> for CurveTriangle {
+    fn aabb(&self) -> Aabb<f32, 3> {
+        let bounds = self.bounds.as_ref().unwrap();
+        let min_p = nalgebra::Point3::new(bounds.min_x, bounds.min_y, bounds.min_z);
+        let max_p = nalgebra::Point3::new(bounds.max_x, bounds.max_y, bounds.max_z);
         Aabb::with_bounds(min_p, max_p)
     }
 }
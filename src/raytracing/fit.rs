@@ -0,0 +1,390 @@
+use cgmath::{InnerSpace, Vector3, Zero};
+
+use super::{
+    curve_triangle::CurveTriangle,
+    triangle::Triangle,
+};
+
+/// Step of the central differences for normals of the targets without analytic ones
+const NORMAL_STEP: f32 = 1e-3;
+
+/// Curve koefs are kept in this range while fitting
+const KOEF_MIN: f64 = 1.;
+const KOEF_MAX: f64 = 8.;
+
+/// First simplex step of the pivot coords relative to the longest base edge, and of the koefs
+const PIVOT_STEP: f64 = 0.1;
+const KOEF_STEP: f64 = 0.25;
+
+/// Surface to fit the patches to, given by its signed distance
+pub trait TargetSurface {
+    fn distance(&self, point: Vector3<f32>) -> f32;
+
+    /// Unit gradient of the distance
+    fn normal(&self, point: Vector3<f32>) -> Vector3<f32> {
+        let axis_difference = |axis: Vector3<f32>| {
+            self.distance(point + axis * NORMAL_STEP) - self.distance(point - axis * NORMAL_STEP)
+        };
+        let gradient = Vector3::new(
+            axis_difference(Vector3::unit_x()),
+            axis_difference(Vector3::unit_y()),
+            axis_difference(Vector3::unit_z()),
+        );
+        if gradient.magnitude2() > 0. {
+            gradient.normalize()
+        } else {
+            gradient
+        }
+    }
+}
+
+/// Any SDF closure is a target
+impl<F: Fn(Vector3<f32>) -> f32> TargetSurface for F {
+    fn distance(&self, point: Vector3<f32>) -> f32 {
+        self(point)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sphere {
+    pub center: Vector3<f32>,
+    pub radius: f32,
+}
+
+/// Infinite cylinder around the line through `origin`
+#[derive(Debug, Clone, Copy)]
+pub struct Cylinder {
+    pub origin: Vector3<f32>,
+    /// Unit direction of the axis
+    pub axis: Vector3<f32>,
+    pub radius: f32,
+}
+
+/// Torus in the plane through `center` perpendicular to `axis`
+#[derive(Debug, Clone, Copy)]
+pub struct Torus {
+    pub center: Vector3<f32>,
+    /// Unit normal of the plane of the torus
+    pub axis: Vector3<f32>,
+    /// From the center to the middle of the tube
+    pub major_radius: f32,
+    /// Of the tube
+    pub minor_radius: f32,
+}
+
+impl TargetSurface for Sphere {
+    fn distance(&self, point: Vector3<f32>) -> f32 {
+        (point - self.center).magnitude() - self.radius
+    }
+
+    fn normal(&self, point: Vector3<f32>) -> Vector3<f32> {
+        (point - self.center).normalize()
+    }
+}
+
+impl Cylinder {
+    fn radial(&self, point: Vector3<f32>) -> Vector3<f32> {
+        let relative = point - self.origin;
+        relative - self.axis * relative.dot(self.axis)
+    }
+}
+
+impl TargetSurface for Cylinder {
+    fn distance(&self, point: Vector3<f32>) -> f32 {
+        self.radial(point).magnitude() - self.radius
+    }
+
+    fn normal(&self, point: Vector3<f32>) -> Vector3<f32> {
+        self.radial(point).normalize()
+    }
+}
+
+impl Torus {
+    /// From the nearest point of the middle circle of the tube to the point
+    fn tube_offset(&self, point: Vector3<f32>) -> Vector3<f32> {
+        let relative = point - self.center;
+        let radial = relative - self.axis * relative.dot(self.axis);
+        let tube_middle = if radial.magnitude2() > 0. {
+            radial.normalize() * self.major_radius
+        } else {
+            Vector3::zero()
+        };
+        relative - tube_middle
+    }
+}
+
+impl TargetSurface for Torus {
+    fn distance(&self, point: Vector3<f32>) -> f32 {
+        self.tube_offset(point).magnitude() - self.minor_radius
+    }
+
+    fn normal(&self, point: Vector3<f32>) -> Vector3<f32> {
+        self.tube_offset(point).normalize()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FitOptions {
+    /// Subdivisions of the base edges for the grid of sampled surface points
+    pub samples: usize,
+    /// Limit of the Nelder-Mead iterations over all restarts
+    pub max_iterations: usize,
+    /// Search stops when the rms distances of the simplex differ less than this
+    pub tolerance: f32,
+    /// Koefs of the start patch are kept when false, only the pivots are fitted
+    pub fit_koefs: bool,
+}
+
+impl Default for FitOptions {
+    fn default() -> Self {
+        FitOptions {
+            samples: 8,
+            max_iterations: 4000,
+            tolerance: 1e-7,
+            fit_koefs: true,
+        }
+    }
+}
+
+/// Distance of the sampled patch surface to the target
+#[derive(Debug, Clone, Copy)]
+pub struct SurfaceError {
+    pub rms: f32,
+    pub max: f32,
+}
+
+/// Result of `fit_curve_triangle`
+#[derive(Debug, Clone, Copy)]
+pub struct FitReport {
+    /// Error of the start patch, pivots by the target normals in the base vertexes
+    pub initial: SurfaceError,
+    pub error: SurfaceError,
+    pub iterations: usize,
+}
+
+/// Distances from the surface points on the barycentric grid with `samples` subdivisions to the target
+pub fn surface_error<T: TargetSurface + ?Sized>(
+    patch: &CurveTriangle,
+    target: &T,
+    samples: usize,
+) -> SurfaceError {
+    let distances: Vec<f32> = sample_barys(samples)
+        .into_iter()
+        .map(|bary| target.distance(patch.get_surface_point_by_bary(bary)).abs())
+        .collect();
+    let sum_sqr: f32 = distances.iter().map(|distance| distance * distance).sum();
+    SurfaceError {
+        rms: (sum_sqr / distances.len() as f32).sqrt(),
+        max: distances.iter().fold(0., |max, &distance| max.max(distance)),
+    }
+}
+
+/// Pivots and curve koefs of the patch over the base that minimise the mean square distance
+/// of the sampled surface to the target. Vertexes of the base should lie on the target.
+/// The search starts from the pivots given by `edge_pivot` for the target normals in the vertexes.
+pub fn fit_curve_triangle<T: TargetSurface + ?Sized>(
+    base: Triangle,
+    target: &T,
+    options: &FitOptions,
+) -> (CurveTriangle, FitReport) {
+    let vertexes = base.vertexes;
+    let normals = vertexes.map(|vertex| target.normal(vertex));
+    let start = CurveTriangle::from_vertex_normals(base.clone(), normals);
+    let initial = surface_error(&start, target, options.samples);
+
+    let vertexes_f64 = vertexes.map(|vertex| vertex.cast::<f64>().unwrap());
+    let barys: Vec<Vector3<f64>> = sample_barys(options.samples)
+        .into_iter()
+        .map(|bary| bary.cast::<f64>().unwrap())
+        .collect();
+    let objective = |params: &[f64]| {
+        let (pivots, curve_koefs) = unpack(params, &start.curve_koefs);
+        let sum_sqr: f64 = barys
+            .iter()
+            .map(|&bary| {
+                let point = CurveTriangle::surface_point_by_bary(&vertexes_f64, &pivots, &curve_koefs, bary);
+                let distance = target.distance(point.cast::<f32>().unwrap()) as f64;
+                distance * distance
+            })
+            .sum();
+        let mean = sum_sqr / barys.len() as f64;
+        if mean.is_nan() {
+            f64::INFINITY
+        } else {
+            mean
+        }
+    };
+
+    let mut params: Vec<f64> = start
+        .pivots
+        .iter()
+        .flat_map(|pivot| [pivot.x as f64, pivot.y as f64, pivot.z as f64])
+        .collect();
+    let mut steps = vec![PIVOT_STEP * longest_edge(&vertexes) as f64; params.len()];
+    if options.fit_koefs {
+        params.extend(start.curve_koefs.iter().map(|&koef| koef as f64));
+        steps.extend([KOEF_STEP; 3]);
+    }
+
+    let (params, iterations) = nelder_mead(
+        &objective,
+        params,
+        &steps,
+        options.max_iterations,
+        options.tolerance as f64,
+    );
+
+    let (pivots, curve_koefs) = unpack(&params, &start.curve_koefs);
+    let patch = CurveTriangle::new(
+        base,
+        pivots.map(|pivot| pivot.cast::<f32>().unwrap()),
+        curve_koefs.map(|koef| koef as f32),
+    );
+    let error = surface_error(&patch, target, options.samples);
+    (
+        patch,
+        FitReport {
+            initial,
+            error,
+            iterations,
+        },
+    )
+}
+
+/// Barycentric grid over the base without the corners, they are on the target anyway
+fn sample_barys(samples: usize) -> Vec<Vector3<f32>> {
+    let samples = samples.max(1);
+    let mut barys = Vec::new();
+    for i in 0..=samples {
+        for j in 0..=samples - i {
+            if (i == 0 && (j == 0 || j == samples)) || i == samples {
+                continue;
+            }
+            let (w1, w2) = (i as f32 / samples as f32, j as f32 / samples as f32);
+            barys.push(Vector3::new((1. - w1 - w2).max(0.), w1, w2));
+        }
+    }
+    barys
+}
+
+/// Pivots and clamped koefs from the search params, koefs are taken from `default_koefs`
+/// when they are not searched
+fn unpack(params: &[f64], default_koefs: &[f32; 3]) -> ([Vector3<f64>; 3], [f64; 3]) {
+    let pivots = [0, 1, 2].map(|index| {
+        Vector3::new(params[index * 3], params[index * 3 + 1], params[index * 3 + 2])
+    });
+    let curve_koefs = match params.get(9..12) {
+        Some(koefs) => [0, 1, 2].map(|index| {
+            // NaN params of a diverged search are the smallest koef
+            if koefs[index].is_nan() {
+                KOEF_MIN
+            } else {
+                koefs[index].clamp(KOEF_MIN, KOEF_MAX)
+            }
+        }),
+        None => default_koefs.map(|koef| koef as f64),
+    };
+    (pivots, curve_koefs)
+}
+
+fn longest_edge(vertexes: &[Vector3<f32>; 3]) -> f32 {
+    (0..3)
+        .map(|edge| (vertexes[(edge + 1) % 3] - vertexes[edge]).magnitude())
+        .fold(0., f32::max)
+}
+
+/// Nelder-Mead minimisation from the axis aligned simplex with `steps` around `start`.
+/// The simplex is rebuilt around the best point while restarts improve it.
+/// Returns the best point and the number of iterations.
+fn nelder_mead<F>(
+    objective: &F,
+    start: Vec<f64>,
+    steps: &[f64],
+    max_iterations: usize,
+    tolerance: f64,
+) -> (Vec<f64>, usize)
+where
+    F: Fn(&[f64]) -> f64,
+{
+    let dimension = start.len();
+    let mut best = start;
+    let mut best_value = objective(&best);
+    let mut iterations = 0;
+
+    while iterations < max_iterations {
+        let mut simplex: Vec<(Vec<f64>, f64)> = vec![(best.clone(), best_value)];
+        for axis in 0..dimension {
+            let mut point = best.clone();
+            point[axis] += steps[axis];
+            let value = objective(&point);
+            simplex.push((point, value));
+        }
+
+        while iterations < max_iterations {
+            simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+            // rms distances of the best and the worst points
+            if simplex[dimension].1.sqrt() - simplex[0].1.sqrt() < tolerance {
+                break;
+            }
+            iterations += 1;
+
+            let mut centroid = vec![0.; dimension];
+            for (point, _) in simplex[..dimension].iter() {
+                for axis in 0..dimension {
+                    centroid[axis] += point[axis] / dimension as f64;
+                }
+            }
+            let towards = |from: &[f64], koef: f64| -> Vec<f64> {
+                (0..dimension)
+                    .map(|axis| centroid[axis] + koef * (from[axis] - centroid[axis]))
+                    .collect()
+            };
+
+            let worst = simplex[dimension].0.clone();
+            let reflected = towards(&worst, -1.);
+            let reflected_value = objective(&reflected);
+            if reflected_value < simplex[0].1 {
+                let expanded = towards(&worst, -2.);
+                let expanded_value = objective(&expanded);
+                simplex[dimension] = if expanded_value < reflected_value {
+                    (expanded, expanded_value)
+                } else {
+                    (reflected, reflected_value)
+                };
+            } else if reflected_value < simplex[dimension - 1].1 {
+                simplex[dimension] = (reflected, reflected_value);
+            } else {
+                let contracted = if reflected_value < simplex[dimension].1 {
+                    towards(&worst, -0.5)
+                } else {
+                    towards(&worst, 0.5)
+                };
+                let contracted_value = objective(&contracted);
+                if contracted_value < reflected_value.min(simplex[dimension].1) {
+                    simplex[dimension] = (contracted, contracted_value);
+                } else {
+                    // shrink to the best point
+                    let first = simplex[0].0.clone();
+                    for (point, value) in simplex[1..].iter_mut() {
+                        for axis in 0..dimension {
+                            point[axis] = first[axis] + 0.5 * (point[axis] - first[axis]);
+                        }
+                        *value = objective(point);
+                    }
+                }
+            }
+        }
+
+        simplex.sort_by(|a, b| a.1.total_cmp(&b.1));
+        let (point, value) = simplex.swap_remove(0);
+        let improved = value.sqrt() < best_value.sqrt() - tolerance;
+        if value < best_value {
+            best = point;
+            best_value = value;
+        }
+        if !improved {
+            break;
+        }
+    }
+    (best, iterations)
+}
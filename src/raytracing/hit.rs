@@ -0,0 +1,58 @@
+use cgmath::{InnerSpace, Vector2, Vector3};
+
+use super::ray::Ray;
+
+/// Ray hit of a surface
+#[derive(Debug, Clone, Copy)]
+pub struct Hit {
+    /// Distance along the ray
+    pub t: f32,
+    pub point: Vector3<f32>,
+    /// Unit normal of the surface at the point, always turned against the ray
+    pub normal: Vector3<f32>,
+    /// Barycentric coords of the hit on the primitive base triangle
+    pub bary: Vector3<f32>,
+    /// Surface coords of the hit
+    pub uv: Vector2<f32>,
+    /// Index of the primitive in its collection, set by the one who owns the collection
+    pub primitive_id: usize,
+    /// Index of the shape in the scene, set by the scene
+    pub shape_id: usize,
+    /// Ray hits the outer side of the surface, the side `outward_normal` points to
+    pub front_face: bool,
+}
+
+impl Hit {
+    /// `outward_normal` must be unit, it is flipped to face the ray
+    pub fn new(ray: &Ray, t: f32, outward_normal: Vector3<f32>, bary: Vector3<f32>) -> Hit {
+        let front_face = ray.direction.dot(outward_normal) < 0.;
+        Hit {
+            t,
+            point: ray.get_point(t),
+            normal: if front_face {
+                outward_normal
+            } else {
+                -outward_normal
+            },
+            bary,
+            uv: Vector2::new(bary.y, bary.z),
+            primitive_id: 0,
+            shape_id: 0,
+            front_face,
+        }
+    }
+
+    /// Normal pointing to the outer side of the surface
+    pub fn outward_normal(&self) -> Vector3<f32> {
+        if self.front_face {
+            self.normal
+        } else {
+            -self.normal
+        }
+    }
+}
+
+/// Primitives that can be hit by a ray, only hits in front of the ray origin count
+pub trait Intersect {
+    fn intersect(&self, ray: &Ray) -> Option<Hit>;
+}
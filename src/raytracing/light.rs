@@ -0,0 +1,164 @@
+use cgmath::{InnerSpace, Vector3};
+
+use crate::{materials::RGB, utils::Rng};
+
+use super::{curve_triangle::CurveTriangle, triangle::Triangle};
+
+/// Light source of the scene
+#[derive(Debug, Clone)]
+pub enum Light {
+    /// Shines from the position in every direction, falls off with the squared distance
+    Point {
+        position: Vector3<f32>,
+        color: RGB,
+        intensity: f32,
+    },
+    /// Shines along the unit direction from infinity
+    Directional {
+        direction: Vector3<f32>,
+        color: RGB,
+        intensity: f32,
+    },
+    /// Emissive surface, see `AreaLight`
+    Area(AreaLight),
+}
+
+/// Emissive triangles shining from their front side, the side their vertexes go counter-clockwise.
+/// `intensity` is the radiance, the same in every direction.
+#[derive(Debug, Clone)]
+pub struct AreaLight {
+    pub triangles: Vec<Triangle>,
+    pub color: RGB,
+    pub intensity: f32,
+    /// Running sum of the areas of the triangles, to pick them by area
+    cumulative_areas: Vec<f32>,
+}
+
+/// Light coming to a point from one light or one point of it
+#[derive(Debug, Clone, Copy)]
+pub struct LightSample {
+    /// Unit direction from the lit point to the light
+    pub direction: Vector3<f32>,
+    /// Distance to the light, infinite for directional lights
+    pub distance: f32,
+    /// Color reaching the lit point, divided by the probability of the sample
+    pub radiance: RGB,
+}
+
+impl Light {
+    /// Point and directional lights come from a single direction, one sample is exact
+    pub fn is_delta(&self) -> bool {
+        !matches!(self, Light::Area(_))
+    }
+
+    /// Light reaching the point, a random point of an area light.
+    /// `None` when the point is behind the sampled emitter.
+    pub fn sample(&self, point: Vector3<f32>, rng: &mut Rng) -> Option<LightSample> {
+        match self {
+            &Light::Point {
+                position,
+                color,
+                intensity,
+            } => {
+                let to_light = position - point;
+                let distance = to_light.magnitude();
+                let falloff = intensity / (distance * distance).max(f32::EPSILON);
+                Some(LightSample {
+                    direction: to_light / distance,
+                    distance,
+                    radiance: color.map(|channel| channel * falloff),
+                })
+            }
+            &Light::Directional {
+                direction,
+                color,
+                intensity,
+            } => Some(LightSample {
+                direction: -direction,
+                distance: f32::INFINITY,
+                radiance: color.map(|channel| channel * intensity),
+            }),
+            Light::Area(area) => area.sample(point, rng),
+        }
+    }
+}
+
+impl AreaLight {
+    pub fn new(triangles: Vec<Triangle>, color: RGB, intensity: f32) -> AreaLight {
+        let mut area = 0.;
+        let cumulative_areas = triangles
+            .iter()
+            .map(|triangle| {
+                area += triangle_area(triangle);
+                area
+            })
+            .collect();
+        AreaLight {
+            triangles,
+            color,
+            intensity,
+            cumulative_areas,
+        }
+    }
+
+    /// Curve patches emitting from their triangulation, the front side looks away from the root points.
+    /// Patches must be triangulated, see `CurveTriangle::triangulate`.
+    pub fn from_patches(patches: &[CurveTriangle], color: RGB, intensity: f32) -> AreaLight {
+        let mut triangles = Vec::new();
+        for patch in patches.iter() {
+            for triangle in patch.triangulation.iter() {
+                let [a, b, c] = triangle.vertexes;
+                let center = (a + b + c) / 3.;
+                triangles.push(if triangle.normal().dot(center - patch.root_point) < 0. {
+                    Triangle::new([a, c, b])
+                } else {
+                    Triangle::new([a, b, c])
+                });
+            }
+        }
+        AreaLight::new(triangles, color, intensity)
+    }
+
+    pub fn area(&self) -> f32 {
+        self.cumulative_areas.last().copied().unwrap_or(0.)
+    }
+
+    /// Uniform point of the emitting surface
+    fn sample(&self, point: Vector3<f32>, rng: &mut Rng) -> Option<LightSample> {
+        let area = self.area();
+        if area.is_nan() || area <= 0. {
+            return None;
+        }
+        let picked = rng.next_f32() * area;
+        let index = self
+            .cumulative_areas
+            .partition_point(|&sum| sum <= picked)
+            .min(self.triangles.len() - 1);
+        let [a, b, c] = self.triangles[index].vertexes;
+        let (mut u, mut v) = (rng.next_f32(), rng.next_f32());
+        if u + v > 1. {
+            (u, v) = (1. - u, 1. - v);
+        }
+        let light_point = a + (b - a) * u + (c - a) * v;
+
+        let to_light = light_point - point;
+        let distance = to_light.magnitude();
+        let direction = to_light / distance;
+        let cosine = -direction.dot(self.triangles[index].normal());
+        if cosine.is_nan() || cosine <= 0. {
+            return None;
+        }
+        // radiance over the probability density of the point in solid angle
+        let weight = self.intensity * cosine * area / (distance * distance).max(f32::EPSILON);
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: self.color.map(|channel| channel * weight),
+        })
+    }
+}
+
+fn triangle_area(triangle: &Triangle) -> f32 {
+    let [a, b, c] = triangle.vertexes;
+    (b - a).cross(c - a).magnitude() * 0.5
+}
@@ -0,0 +1,406 @@
+use std::collections::HashMap;
+
+use bvh::{
+    aabb::{Aabb, Bounded},
+    bounding_hierarchy::BHShape,
+};
+use cgmath::{InnerSpace, Vector2, Vector3};
+
+use crate::utils::MinMaxIterExt;
+
+use super::{
+    curve_triangle::{bary_edge_length, CurveTriangle, BASE_BARY, EDGE_TOLERANCE_PART},
+    hit::Hit,
+    ray::Ray,
+    triangle::Triangle,
+};
+
+/// Base vertexes of different patches closer than this are the same vertex
+const WELD_DISTANCE: f32 = 1e-5;
+
+/// Indexed triangle mesh, faces share vertexes of one buffer.
+/// Optional attributes have a value per vertex.
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub vertexes: Vec<Vector3<f32>>,
+    pub indices: Vec<[u32; 3]>,
+
+    /// Unit normals of the source surface
+    pub normals: Option<Vec<Vector3<f32>>>,
+    pub uvs: Option<Vec<Vector2<f32>>>,
+    /// Where the vertex is on the source curve patch
+    pub patch_barys: Option<Vec<PatchBary>>,
+}
+
+/// Point of a curve patch by barycentric coords of its base
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PatchBary {
+    /// Index of the patch in the tessellated slice
+    pub patch: usize,
+    pub bary: Vector3<f32>,
+}
+
+/// Face of a mesh referenced by index, so a BVH can be built over the faces
+/// without copying them into triangles
+#[derive(Debug, Clone)]
+pub struct MeshFace {
+    pub face: usize,
+    aabb: Aabb<f32, 3>,
+    bhv_node_index: usize,
+}
+
+/// Welded vertex of the tessellation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum VertexKey {
+    /// Welded base vertex
+    Corner(usize),
+    /// Point of a shared base edge by its parameter from the lower corner
+    Edge((usize, usize), u32),
+    /// Inner point of a patch by its barycentric coords
+    Inner(usize, [u32; 3]),
+}
+
+impl Mesh {
+    /// Weld triangulations of the patches by equal vertexes, without attributes
+    pub fn from_triangulations(patches: &[CurveTriangle]) -> Mesh {
+        let mut mesh = Mesh::default();
+        let mut vertex_ids: HashMap<[u32; 3], u32> = HashMap::new();
+        for triangle in patches.iter().flat_map(|patch| patch.triangulation.iter()) {
+            let face = triangle.vertexes.map(|vertex| {
+                *vertex_ids
+                    .entry(vertex.map(f32::to_bits).into())
+                    .or_insert_with(|| {
+                        mesh.vertexes.push(vertex);
+                        (mesh.vertexes.len() - 1) as u32
+                    })
+            });
+            mesh.indices.push(face);
+        }
+        mesh
+    }
+
+    /// Add faces of the other mesh without welding. Patches of `other` are numbered after
+    /// `patch_offset` patches of this one. Attributes stay only when both meshes have them.
+    pub fn append(&mut self, other: &Mesh, patch_offset: usize) {
+        let first_vertex = self.vertexes.len() as u32;
+        let empty = self.vertexes.is_empty();
+        fn merge<T: Copy>(own: &mut Option<Vec<T>>, other: &Option<Vec<T>>, empty: bool, map: impl Fn(T) -> T) {
+            *own = match (own.take(), other) {
+                (Some(mut own), Some(other)) => {
+                    own.extend(other.iter().copied().map(map));
+                    Some(own)
+                }
+                (None, Some(other)) if empty => Some(other.iter().copied().map(map).collect()),
+                _ => None,
+            };
+        }
+        merge(&mut self.normals, &other.normals, empty, |normal| normal);
+        merge(&mut self.uvs, &other.uvs, empty, |uv| uv);
+        merge(&mut self.patch_barys, &other.patch_barys, empty, |patch_bary| PatchBary {
+            patch: patch_bary.patch + patch_offset,
+            ..patch_bary
+        });
+
+        self.vertexes.extend(other.vertexes.iter().copied());
+        self.indices
+            .extend(other.indices.iter().map(|face| face.map(|index| index + first_vertex)));
+    }
+
+    /// Area weighted normals of the faces around every vertex
+    pub fn compute_normals(&mut self) {
+        self.normals = Some(self.area_weighted_normals());
+    }
+
+    fn area_weighted_normals(&self) -> Vec<Vector3<f32>> {
+        let mut normals = vec![Vector3::new(0., 0., 0.); self.vertexes.len()];
+        for face in self.indices.iter() {
+            let [a, b, c] = face.map(|index| self.vertexes[index as usize]);
+            let area_normal = (b - a).cross(c - a);
+            for &index in face.iter() {
+                normals[index as usize] += area_normal;
+            }
+        }
+        for normal in normals.iter_mut() {
+            if normal.magnitude2() > 0. {
+                *normal = normal.normalize();
+            }
+        }
+        normals
+    }
+
+    /// Curve patch per face tangent to the vertex normals, see `CurveTriangle::from_vertex_normals`.
+    /// Normals are computed from the faces when the mesh has none.
+    pub fn to_curve_patches(&self) -> Vec<CurveTriangle> {
+        let computed;
+        let normals = match self.normals.as_ref() {
+            Some(normals) => normals,
+            None => {
+                computed = self.area_weighted_normals();
+                &computed
+            }
+        };
+
+        (0..self.indices.len())
+            .map(|face| {
+                let normals = self.indices[face].map(|index| normals[index as usize]);
+                CurveTriangle::from_vertex_normals(self.face(face), normals)
+            })
+            .collect()
+    }
+
+    pub fn face(&self, face: usize) -> Triangle {
+        let [a, b, c] = self.indices[face];
+        Triangle::new([
+            self.vertexes[a as usize],
+            self.vertexes[b as usize],
+            self.vertexes[c as usize],
+        ])
+    }
+
+    pub fn triangles(&self) -> impl Iterator<Item = Triangle> + '_ {
+        (0..self.indices.len()).map(|face| self.face(face))
+    }
+
+    /// Faces for a BVH over the mesh
+    pub fn faces(&self) -> Vec<MeshFace> {
+        (0..self.indices.len())
+            .map(|face| {
+                let corners = self.indices[face].map(|index| self.vertexes[index as usize]);
+                let bound = |axis: usize| corners.iter().map(|corner| corner[axis]).min_max();
+                let ((min_x, max_x), (min_y, max_y), (min_z, max_z)) = (bound(0), bound(1), bound(2));
+                MeshFace {
+                    face,
+                    aabb: Aabb::with_bounds(
+                        nalgebra::Point3::new(min_x, min_y, min_z),
+                        nalgebra::Point3::new(max_x, max_y, max_z),
+                    ),
+                    bhv_node_index: 0,
+                }
+            })
+            .collect()
+    }
+
+    /// Hit of the face by Möller–Trumbore on the vertex buffer,
+    /// normal and uv are interpolated when the mesh has them
+    pub fn intersect_face(&self, face: usize, ray: &Ray) -> Option<Hit> {
+        let indices = self.indices[face].map(|index| index as usize);
+        let [a, b, c] = indices.map(|index| self.vertexes[index]);
+        let (e1, e2) = (b - a, c - a);
+
+        let p = ray.direction.cross(e2);
+        let det = e1.dot(p);
+        // the ray is parallel to the face
+        if det == 0. {
+            return None;
+        }
+        let inverse_det = 1. / det;
+        let s = ray.origin - a;
+        let w1 = s.dot(p) * inverse_det;
+        if !(0. ..=1.).contains(&w1) {
+            return None;
+        }
+        let q = s.cross(e1);
+        let w2 = ray.direction.dot(q) * inverse_det;
+        if w2 < 0. || w1 + w2 > 1. {
+            return None;
+        }
+        let t = e2.dot(q) * inverse_det;
+        if t.is_nan() || t <= 0. {
+            return None;
+        }
+        let bary = Vector3::new(1. - w1 - w2, w1, w2);
+        let face_normal = || e1.cross(e2).normalize();
+
+        let normal = match self.normals.as_ref() {
+            Some(normals) => {
+                let normal = normals[indices[0]] * bary.x
+                    + normals[indices[1]] * bary.y
+                    + normals[indices[2]] * bary.z;
+                if normal.magnitude2() > 0. {
+                    normal.normalize()
+                } else {
+                    face_normal()
+                }
+            }
+            None => face_normal(),
+        };
+        let mut hit = Hit::new(ray, t, normal, bary);
+        if let Some(uvs) = self.uvs.as_ref() {
+            hit.uv = uvs[indices[0]] * bary.x + uvs[indices[1]] * bary.y + uvs[indices[2]] * bary.z;
+        }
+        hit.primitive_id = face;
+        Some(hit)
+    }
+
+    /// Adaptive tessellation of the patches into one welded mesh, like
+    /// `CurveTriangle::triangulate_adaptive`. Patches are adjacent when their base
+    /// vertexes match, a shared edge is split where any of its patches needs it,
+    /// so both sides get the same points and there are no cracks or T-junctions.
+    /// Points of a shared edge are taken from the first patch.
+    /// Faces are wound counter-clockwise looking from outside of the root points.
+    /// Vertexes get normals of the patch, uv and barycentric coords of the patch base.
+    pub fn tessellate(patches: &[CurveTriangle], tolerance: f32, max_depth: u32) -> Mesh {
+        let min_edge = 0.5f32.powi(max_depth as i32);
+        let edge_tolerance = tolerance * EDGE_TOLERANCE_PART;
+
+        // weld base vertexes, close ones are in the same or the neighbour cells of the grid
+        let mut corners: Vec<Vector3<f32>> = Vec::new();
+        let mut corner_cells: HashMap<[i64; 3], Vec<usize>> = HashMap::new();
+        let corner_ids: Vec<[usize; 3]> = patches
+            .iter()
+            .map(|patch| {
+                patch.base.vertexes.map(|vertex| {
+                    let cell = weld_cell(vertex);
+                    let welded = neighbour_cells(cell)
+                        .filter_map(|neighbour| corner_cells.get(&neighbour))
+                        .flatten()
+                        .copied()
+                        .find(|&id| (corners[id] - vertex).magnitude() < WELD_DISTANCE);
+                    welded.unwrap_or_else(|| {
+                        corners.push(vertex);
+                        corner_cells.entry(cell).or_default().push(corners.len() - 1);
+                        corners.len() - 1
+                    })
+                })
+            })
+            .collect();
+
+        // patches around every base edge
+        let mut edges: HashMap<(usize, usize), Vec<(usize, usize)>> = HashMap::new();
+        for (patch_id, ids) in corner_ids.iter().enumerate() {
+            for edge in 0..3 {
+                let key = edge_key(ids[edge], ids[(edge + 1) % 3]);
+                edges.entry(key).or_default().push((patch_id, edge));
+            }
+        }
+
+        // point of the base edge of the patch by the parameter of the shared edge
+        let edge_bary = |patch_id: usize, edge: usize, s: f32| {
+            let ids = corner_ids[patch_id];
+            let s = if ids[edge] < ids[(edge + 1) % 3] { s } else { 1. - s };
+            let mut bary = Vector3::new(0., 0., 0.);
+            bary[edge] = 1. - s;
+            bary[(edge + 1) % 3] = s;
+            bary
+        };
+        // base edge of the patch and the parameter of the point on it, if the point is on a border
+        let on_border = |patch_id: usize, bary: Vector3<f32>| {
+            (0..3).find(|&edge| bary[(edge + 2) % 3] == 0.).map(|edge| {
+                let ids = corner_ids[patch_id];
+                let s = bary[(edge + 1) % 3];
+                let s = if ids[edge] < ids[(edge + 1) % 3] { s } else { 1. - s };
+                (edge, s)
+            })
+        };
+
+        let mut mesh = Mesh::default();
+        let (mut normals, mut uvs, mut patch_barys) = (Vec::new(), Vec::new(), Vec::new());
+        let mut vertex_ids: HashMap<VertexKey, u32> = HashMap::new();
+
+        for (patch_id, patch) in patches.iter().enumerate() {
+            let ids = corner_ids[patch_id];
+            let needs_split = |a: Vector3<f32>, b: Vector3<f32>| {
+                if bary_edge_length(a, b) <= min_edge {
+                    return false;
+                }
+                let border = (0..3).find(|&edge| {
+                    let opposite = (edge + 2) % 3;
+                    a[opposite] == 0. && b[opposite] == 0.
+                });
+                match border {
+                    Some(edge) => {
+                        let (s_a, s_b) = (a[(edge + 1) % 3], b[(edge + 1) % 3]);
+                        let (s_a, s_b) = if ids[edge] < ids[(edge + 1) % 3] {
+                            (s_a, s_b)
+                        } else {
+                            (1. - s_a, 1. - s_b)
+                        };
+                        edges[&edge_key(ids[edge], ids[(edge + 1) % 3])]
+                            .iter()
+                            .any(|&(other_id, other_edge)| {
+                                patches[other_id].edge_error(
+                                    edge_bary(other_id, other_edge, s_a),
+                                    edge_bary(other_id, other_edge, s_b),
+                                ) > edge_tolerance
+                            })
+                    }
+                    None => patch.edge_error(a, b) > edge_tolerance,
+                }
+            };
+
+            // faces look away from the root point, like normals of the patch
+            let base = patch.base.vertexes;
+            let base_center = (base[0] + base[1] + base[2]) / 3.;
+            let winding = if patch.base.normal().dot(base_center - patch.root_point) < 0. {
+                [BASE_BARY[0], BASE_BARY[2], BASE_BARY[1]]
+            } else {
+                BASE_BARY
+            };
+
+            let mut bary_triangles = Vec::new();
+            patch.subdivide_bary(winding, max_depth, &needs_split, &mut bary_triangles);
+
+            for corners_bary in bary_triangles {
+                let face = corners_bary.map(|bary| {
+                    let key = match BASE_BARY.iter().position(|&corner| corner == bary) {
+                        Some(corner) => VertexKey::Corner(ids[corner]),
+                        None => match on_border(patch_id, bary) {
+                            Some((edge, s)) => {
+                                VertexKey::Edge(edge_key(ids[edge], ids[(edge + 1) % 3]), s.to_bits())
+                            }
+                            None => VertexKey::Inner(patch_id, bary.map(f32::to_bits).into()),
+                        },
+                    };
+                    *vertex_ids.entry(key).or_insert_with(|| {
+                        mesh.vertexes.push(patch.get_surface_point_by_bary(bary));
+                        normals.push(patch.normal_at_bary(bary));
+                        uvs.push(Vector2::new(bary.y, bary.z));
+                        patch_barys.push(PatchBary {
+                            patch: patch_id,
+                            bary,
+                        });
+                        (mesh.vertexes.len() - 1) as u32
+                    })
+                });
+                mesh.indices.push(face);
+            }
+        }
+        mesh.normals = Some(normals);
+        mesh.uvs = Some(uvs);
+        mesh.patch_barys = Some(patch_barys);
+        mesh
+    }
+}
+
+impl Bounded<f32, 3> for MeshFace {
+    fn aabb(&self) -> Aabb<f32, 3> {
+        self.aabb
+    }
+}
+
+impl BHShape<f32, 3> for MeshFace {
+    fn set_bh_node_index(&mut self, index: usize) {
+        self.bhv_node_index = index
+    }
+
+    fn bh_node_index(&self) -> usize {
+        self.bhv_node_index
+    }
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// Cell of the point in the grid of `WELD_DISTANCE` steps
+fn weld_cell(point: Vector3<f32>) -> [i64; 3] {
+    point.map(|coord| (coord / WELD_DISTANCE).floor() as i64).into()
+}
+
+/// The cell and the 26 cells around it
+fn neighbour_cells(cell: [i64; 3]) -> impl Iterator<Item = [i64; 3]> {
+    (0..27).map(move |index| {
+        let offset = [index % 3 - 1, index / 3 % 3 - 1, index / 9 - 1];
+        [cell[0] + offset[0], cell[1] + offset[1], cell[2] + offset[2]]
+    })
+}
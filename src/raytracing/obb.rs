@@ -1 +1,146 @@
+use cgmath::{ElementWise, InnerSpace, Vector3};
 
+use crate::utils::MinMaxIterExt;
+
+use super::{aabb::AABBox, curve_triangle::CurveTriangle, ray::Ray};
+
+/// Oriented bounding box
+#[derive(Debug, Clone, Copy)]
+pub struct Obb {
+    pub center: Vector3<f32>,
+    /// Orthonormal axes of the box
+    pub axes: [Vector3<f32>; 3],
+    /// Half sizes along the axes
+    pub half_size: Vector3<f32>,
+}
+
+impl Obb {
+    /// Smallest box with the given orthonormal axes containing the points
+    pub fn with_axes(axes: [Vector3<f32>; 3], points: &[Vector3<f32>]) -> Obb {
+        let mut center = Vector3::new(0., 0., 0.);
+        let mut half_size = Vector3::new(0., 0., 0.);
+        for (index, axis) in axes.iter().enumerate() {
+            let (min, max) = points.iter().map(|point| axis.dot(*point)).min_max();
+            center += axis * (min + max) * 0.5;
+            half_size[index] = (max - min) * 0.5;
+        }
+        Obb {
+            center,
+            axes,
+            half_size,
+        }
+    }
+
+    /// Box along the principal components of the points
+    pub fn from_points(points: &[Vector3<f32>]) -> Obb {
+        let mean = points
+            .iter()
+            .fold(Vector3::new(0., 0., 0.), |sum, point| sum + point)
+            / points.len() as f32;
+
+        let mut covariance = nalgebra::Matrix3::<f32>::zeros();
+        for point in points {
+            let shift = nalgebra::Vector3::new(point.x - mean.x, point.y - mean.y, point.z - mean.z);
+            covariance += shift * shift.transpose();
+        }
+        let eigen = nalgebra::SymmetricEigen::new(covariance);
+
+        let axis = |index: usize| {
+            let column = eigen.eigenvectors.column(index);
+            Vector3::new(column[0], column[1], column[2]).normalize()
+        };
+        let (first, second) = (axis(0), axis(1));
+        // rebuild the last axis so the frame stays orthonormal with repeated eigenvalues
+        let second = (second - first * first.dot(second)).normalize();
+        Obb::with_axes([first, second, first.cross(second)], points)
+    }
+
+    /// Box of the patch shell aligned with its base: the longest base edge,
+    /// the base normal and the direction across them
+    pub fn from_curve_triangle(curve_triangle: &CurveTriangle) -> Obb {
+        let vertexes = curve_triangle.base.vertexes;
+        let edge = [
+            vertexes[1] - vertexes[0],
+            vertexes[2] - vertexes[1],
+            vertexes[0] - vertexes[2],
+        ]
+        .into_iter()
+        .max_by(|a, b| a.magnitude2().total_cmp(&b.magnitude2()))
+        .unwrap()
+        .normalize();
+        let normal = curve_triangle.base.normal();
+        let across = normal.cross(edge);
+
+        let mut points = vertexes.to_vec();
+        points.extend(curve_triangle.shell_points);
+        points.push(curve_triangle.root_point);
+        Obb::with_axes([edge, across, normal], &points)
+    }
+
+    /// Part of the ray inside the box, `None` if the ray misses it
+    pub fn get_slice_for_ray(&self, ray: &Ray) -> Option<(f32, f32)> {
+        // slab test of AABBox in the frame of the box
+        let shift = ray.origin - self.center;
+        let origin = Vector3::new(
+            self.axes[0].dot(shift),
+            self.axes[1].dot(shift),
+            self.axes[2].dot(shift),
+        );
+        let direction = Vector3::new(
+            self.axes[0].dot(ray.direction),
+            self.axes[1].dot(ray.direction),
+            self.axes[2].dot(ray.direction),
+        );
+
+        // per axis, so a ray parallel to a slab gets infinite distances of the same sign
+        // outside of it instead of NaN
+        let inv_dir = 1.0 / direction;
+        let t_min = (-self.half_size - origin).mul_element_wise(inv_dir);
+        let t_max = (self.half_size - origin).mul_element_wise(inv_dir);
+        let (t1, t2) = (t_min.zip(t_max, f32::min), t_min.zip(t_max, f32::max));
+
+        let t_n = t1.x.max(t1.y.max(t1.z));
+        let t_f = t2.x.min(t2.y.min(t2.z));
+
+        // no intersection
+        if t_n > t_f || t_f < 0.0 {
+            return None;
+        }
+
+        Some((t_n.max(0.), t_f))
+    }
+
+    pub fn corners(&self) -> [Vector3<f32>; 8] {
+        let [x, y, z] = [
+            self.axes[0] * self.half_size.x,
+            self.axes[1] * self.half_size.y,
+            self.axes[2] * self.half_size.z,
+        ];
+        [
+            self.center - x - y - z,
+            self.center + x - y - z,
+            self.center - x + y - z,
+            self.center + x + y - z,
+            self.center - x - y + z,
+            self.center + x - y + z,
+            self.center - x + y + z,
+            self.center + x + y + z,
+        ]
+    }
+
+    /// Axis aligned box around the oriented one
+    pub fn to_aabb(&self) -> AABBox {
+        let corners = self.corners();
+        let (min_x, max_x) = corners.iter().map(|v| v.x).min_max();
+        let (min_y, max_y) = corners.iter().map(|v| v.y).min_max();
+        let (min_z, max_z) = corners.iter().map(|v| v.z).min_max();
+        AABBox {
+            min_x,
+            max_x,
+            min_y,
+            max_y,
+            min_z,
+            max_z,
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use std::f32::consts::PI;
+
+use cgmath::{InnerSpace, Vector3};
+
+use crate::{materials::Material, utils::Rng};
+
+use super::{
+    hit::Hit,
+    light::Light,
+    ray::Ray,
+    renderer::Renderer,
+    scene::Scene,
+    shading::{headlight, reflected_light, SHADOW_BIAS},
+    whitted_raytracing::{refract, schlick},
+};
+
+/// Monte Carlo path tracer: one random path per camera ray, average many of them per pixel.
+///
+/// Every bounce adds the light of the lights reflected by the opaque part of the material,
+/// one point of area lights (next event estimation), then the path goes on in a random
+/// direction: cosine-weighted for the diffuse part, a Phong lobe around the mirror direction
+/// for the highlight part, reflected or refracted by Fresnel for the transparent part,
+/// see `WhittedRenderer`. Paths end after `max_depth` bounces, or earlier by Russian roulette.
+pub struct PathTracedRenderer {
+    /// Bounces after the camera ray, 0 is the shaded renderer without ambient light
+    pub max_depth: u32,
+}
+
+/// Bounces before Russian roulette may end the path
+const ROULETTE_DEPTH: u32 = 3;
+
+/// Direction of the path after a bounce and the part of the light it carries
+struct Bounce {
+    ray: Ray,
+    /// Reflected or transmitted color over the probability of the direction
+    weight: [f32; 3],
+}
+
+impl Renderer for PathTracedRenderer {
+    fn shade(&self, ray: &Ray, scene: &Scene) -> [f32; 3] {
+        // every pass of a pixel has another ray, so another path
+        let mut rng = Rng::from_ray(ray);
+        let headlight = headlight(ray);
+        let lights: &[Light] = if scene.lights.is_empty() {
+            &headlight
+        } else {
+            &scene.lights
+        };
+
+        let mut color = [0.; 3];
+        let mut throughput = [1.; 3];
+        let mut ray = Ray {
+            origin: ray.origin,
+            direction: ray.direction,
+        };
+        for depth in 0..=self.max_depth {
+            let hit = match scene.nearest_curve_hit(&ray) {
+                Some(hit) => hit,
+                None => break,
+            };
+            let material = scene.hit_material(&hit);
+            let opacity = material.alpha.clamp(0., 1.);
+            if opacity > 0. {
+                let direct = reflected_light(scene, lights, &ray, &hit, &material, 1, &mut rng);
+                for ((channel, carried), light) in color.iter_mut().zip(throughput).zip(direct) {
+                    *channel += carried * opacity * light;
+                }
+            }
+            if depth == self.max_depth {
+                break;
+            }
+
+            let bounce = match sample_bounce(&ray, &hit, &material, opacity, &mut rng) {
+                Some(bounce) => bounce,
+                None => break,
+            };
+            for (carried, weight) in throughput.iter_mut().zip(bounce.weight) {
+                *carried *= weight;
+            }
+            if depth + 1 >= ROULETTE_DEPTH {
+                // dim paths go on rarely but carry more, the mean stays the same
+                let survival = throughput.iter().copied().fold(0., f32::max).min(1.);
+                if survival.is_nan() || rng.next_f32() >= survival {
+                    break;
+                }
+                throughput = throughput.map(|channel| channel / survival);
+            }
+            ray = bounce.ray;
+        }
+        color
+    }
+}
+
+/// Random direction of the path after the hit, picked by the parts of the material,
+/// `None` when the path is absorbed
+fn sample_bounce(
+    ray: &Ray,
+    hit: &Hit,
+    material: &Material,
+    opacity: f32,
+    rng: &mut Rng,
+) -> Option<Bounce> {
+    let diffuse = opacity * material.diffusion_koefficient.clamp(0., 1.);
+    let specular = opacity * material.specular_koefficient();
+    let transparency = 1. - opacity;
+    let total = diffuse + specular + transparency;
+    if total.is_nan() || total <= 0. {
+        return None;
+    }
+
+    let direction = ray.direction.normalize();
+    let picked = rng.next_f32() * total;
+    let reflected_origin = hit.point + hit.normal * SHADOW_BIAS;
+    if picked < diffuse {
+        let bounce_direction = cosine_sample(hit.normal, rng);
+        return Some(Bounce {
+            ray: Ray {
+                origin: reflected_origin,
+                direction: bounce_direction,
+            },
+            weight: material.color.map(|channel| channel * total),
+        });
+    }
+    if picked < diffuse + specular {
+        let mirror = direction - hit.normal * 2. * direction.dot(hit.normal);
+        // Phong exponent about as wide as the Blinn-Phong highlight of the shininess
+        let bounce_direction = phong_sample(mirror, (material.shininess / 4.).max(1.), rng);
+        let cosine = bounce_direction.dot(hit.normal);
+        if cosine.is_nan() || cosine <= 0. {
+            return None;
+        }
+        return Some(Bounce {
+            ray: Ray {
+                origin: reflected_origin,
+                direction: bounce_direction,
+            },
+            weight: [total; 3],
+        });
+    }
+
+    let ior = material.refraction_koefficient.max(f32::EPSILON);
+    let relative_ior = if hit.front_face { ior } else { 1. / ior };
+    let refracted = refract(direction, hit.normal, relative_ior);
+    let fresnel = match refracted {
+        Some(refracted) => schlick(direction, refracted, hit.normal, relative_ior),
+        None => 1.,
+    };
+    match refracted.filter(|_| rng.next_f32() >= fresnel) {
+        Some(refracted) => Some(Bounce {
+            ray: Ray {
+                origin: hit.point - hit.normal * SHADOW_BIAS,
+                direction: refracted,
+            },
+            // the material color filters the light passing it
+            weight: material.color.map(|channel| channel * total),
+        }),
+        None => Some(Bounce {
+            ray: Ray {
+                origin: reflected_origin,
+                direction: direction - hit.normal * 2. * direction.dot(hit.normal),
+            },
+            weight: [total; 3],
+        }),
+    }
+}
+
+/// Unit direction around the unit normal with density proportional to the cosine,
+/// the cosine of Lambert cancels out with it
+fn cosine_sample(normal: Vector3<f32>, rng: &mut Rng) -> Vector3<f32> {
+    let radius = rng.next_f32().sqrt();
+    let angle = 2. * PI * rng.next_f32();
+    let height = (1. - radius * radius).max(0.).sqrt();
+    from_local(normal, angle.cos() * radius, angle.sin() * radius, height)
+}
+
+/// Unit direction around the unit axis with density proportional to the cosine to the power
+fn phong_sample(axis: Vector3<f32>, exponent: f32, rng: &mut Rng) -> Vector3<f32> {
+    let cosine = rng.next_f32().powf(1. / (exponent + 1.));
+    let sine = (1. - cosine * cosine).max(0.).sqrt();
+    let angle = 2. * PI * rng.next_f32();
+    from_local(axis, angle.cos() * sine, angle.sin() * sine, cosine)
+}
+
+/// Vector of coords in a basis with the unit `z_axis`, normalized
+fn from_local(z_axis: Vector3<f32>, x: f32, y: f32, z: f32) -> Vector3<f32> {
+    // orthonormal basis without branches on the direction, Duff et al. 2017
+    let sign = 1f32.copysign(z_axis.z);
+    let a = -1. / (sign + z_axis.z);
+    let b = z_axis.x * z_axis.y * a;
+    let x_axis = Vector3::new(
+        1. + sign * z_axis.x * z_axis.x * a,
+        sign * b,
+        -sign * z_axis.x,
+    );
+    let y_axis = Vector3::new(b, sign + z_axis.y * z_axis.y * a, -z_axis.y);
+    (x_axis * x + y_axis * y + z_axis * z).normalize()
+}
@@ -0,0 +1,72 @@
+use crate::cpu_buffer::CPUBuffer;
+
+use super::{
+    camera::Camera,
+    renderer::{draw_pass, Renderer},
+    renderer_strips,
+    scene::Scene,
+    tiles::TileScheduler,
+};
+
+/// HDR sums of the rendering passes, every pass adds one sample per pixel of every renderer strip.
+/// The image is the mean of the passes, it converges as they are added.
+pub struct Accumulation {
+    /// Unclamped sums of the passes
+    sums: CPUBuffer,
+    /// The last pass
+    pass: CPUBuffer,
+    pub passes: u32,
+}
+
+impl Accumulation {
+    pub fn new(width: u32, height: u32) -> Accumulation {
+        Accumulation {
+            sums: CPUBuffer::new(width, height),
+            pass: CPUBuffer::new(width, height),
+            passes: 0,
+        }
+    }
+
+    /// Render one more sample per pixel with every renderer in its strip, see `draw_to`.
+    /// Camera ratio should be `strip width / height`.
+    pub fn add_pass(
+        &mut self,
+        camera: &Camera,
+        scene: &Scene,
+        renderers: &[Box<dyn Renderer>],
+        scheduler: &TileScheduler,
+    ) {
+        let strips = renderer_strips(self.sums.width, self.sums.height, renderers.len());
+        for (renderer, strip) in renderers.iter().zip(strips.iter()) {
+            draw_pass(
+                &mut self.pass,
+                strip,
+                camera,
+                scene,
+                renderer.as_ref(),
+                scheduler,
+                self.passes,
+            );
+        }
+        for y in 0..self.sums.height as usize {
+            for x in 0..self.sums.width as usize {
+                let sample = self.pass[(x, y)];
+                let sum = &mut self.sums[(x, y)];
+                for channel in 0..3 {
+                    sum[channel] += sample[channel];
+                }
+            }
+        }
+        self.passes += 1;
+    }
+
+    /// Mean of the passes into the buffer of the same size, black without passes
+    pub fn resolve(&self, cpu_buffer: &mut CPUBuffer) {
+        let scale = 1. / self.passes.max(1) as f32;
+        for y in 0..self.sums.height.min(cpu_buffer.height) as usize {
+            for x in 0..self.sums.width.min(cpu_buffer.width) as usize {
+                cpu_buffer[(x, y)] = self.sums[(x, y)].map(|channel| channel * scale);
+            }
+        }
+    }
+}
@@ -1,9 +1,8 @@
 use cgmath::Vector3;
-use glium::Rect;
 
-use crate::cpu_buffer::CPUBuffer;
+use crate::cpu_buffer::{CPUBuffer, Rect};
 
-use super::{camera::Camera, triangle::Triangle, CurveTriangle};
+use super::{camera::Camera, curve_triangle::CurveTriangle, triangle::Triangle};
 
 pub fn draw_rect_for_triangles(
     cpu_buffer: &mut CPUBuffer,
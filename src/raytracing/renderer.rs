@@ -0,0 +1,170 @@
+use std::{fmt, str::FromStr};
+
+use cgmath::Vector3;
+
+use crate::cpu_buffer::{CPUBuffer, Rect};
+
+use super::{
+    camera::Camera, common_raytracing::TriangulatedRenderer,
+    curve_raytracing::CurveSurfaceRenderer, path_raytracing::PathTracedRenderer, ray::Ray,
+    scene::Scene,
+    shaded_raytracing::ShadedRenderer, tiles::TileScheduler, trihedral_traycing::TrihedralRenderer,
+    whitted_raytracing::WhittedRenderer,
+};
+
+/// Bounces of the reflected, refracted and path rays when the scene doesn't set them
+pub const DEFAULT_TRACE_DEPTH: u32 = 5;
+
+/// Computes color of a single camera ray
+pub trait Renderer: Sync {
+    fn shade(&self, ray: &Ray, scene: &Scene) -> [f32; 3];
+}
+
+/// Renderers selectable at runtime
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RendererKind {
+    Triangulated,
+    CurveSurface,
+    Trihedral,
+    /// Curve surfaces lit by the lights, see `shading::direct_lighting`
+    Shaded,
+    /// Shaded with recursive reflection and refraction, see `WhittedRenderer`
+    Whitted,
+    /// Global illumination by random paths, see `PathTracedRenderer`
+    PathTraced,
+}
+
+impl RendererKind {
+    pub const ALL: [RendererKind; 6] = [
+        RendererKind::Triangulated,
+        RendererKind::CurveSurface,
+        RendererKind::Trihedral,
+        RendererKind::Shaded,
+        RendererKind::Whitted,
+        RendererKind::PathTraced,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RendererKind::Triangulated => "triangulated",
+            RendererKind::CurveSurface => "curve",
+            RendererKind::Trihedral => "trihedral",
+            RendererKind::Shaded => "shaded",
+            RendererKind::Whitted => "whitted",
+            RendererKind::PathTraced => "path",
+        }
+    }
+
+    /// `trace_depth` limits the bounces of the renderers tracing secondary rays
+    pub fn renderer(&self, trace_depth: u32) -> Box<dyn Renderer> {
+        match self {
+            RendererKind::Triangulated => Box::new(TriangulatedRenderer),
+            RendererKind::CurveSurface => Box::new(CurveSurfaceRenderer),
+            RendererKind::Trihedral => Box::new(TrihedralRenderer),
+            RendererKind::Shaded => Box::new(ShadedRenderer),
+            RendererKind::Whitted => Box::new(WhittedRenderer {
+                max_depth: trace_depth,
+            }),
+            RendererKind::PathTraced => Box::new(PathTracedRenderer {
+                max_depth: trace_depth,
+            }),
+        }
+    }
+}
+
+impl fmt::Display for RendererKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for RendererKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        RendererKind::ALL
+            .iter()
+            .find(|kind| kind.name() == s)
+            .copied()
+            .ok_or_else(|| {
+                let names: Vec<&str> = RendererKind::ALL.iter().map(|kind| kind.name()).collect();
+                format!("unknown renderer `{}`, expected one of {}", s, names.join(", "))
+            })
+    }
+}
+
+/// Cast `samples_per_pixel` rays per pixel of the rect and average their colors,
+/// the rect covers the whole camera viewport. A single sample goes through the pixel corner,
+/// more samples are spread over the pixel, see `pixel_sample_offset`.
+pub fn draw_rect(
+    cpu_buffer: &mut CPUBuffer,
+    rect: &Rect,
+    camera: &Camera,
+    scene: &Scene,
+    renderer: &dyn Renderer,
+    scheduler: &TileScheduler,
+    samples_per_pixel: u32,
+) {
+    let samples_per_pixel = samples_per_pixel.max(1);
+    scheduler.render(cpu_buffer, rect, |x, y| {
+        let mut color = [0.; 3];
+        for sample in 0..samples_per_pixel {
+            let (offset_x, offset_y) = pixel_sample_offset(sample, samples_per_pixel);
+            let ray = pixel_ray(camera, rect, x as f32 + offset_x, y as f32 + offset_y);
+            let sample_color = renderer.shade(&ray, scene);
+            for channel in 0..3 {
+                color[channel] += sample_color[channel] / samples_per_pixel as f32;
+            }
+        }
+        color
+    });
+}
+
+/// One sample per pixel of the rect at the `pass`-th offset of `pixel_sample_offset`,
+/// so `n` passes added up sample the same points as `draw_rect` with `n` samples per pixel.
+/// See `progressive::Accumulation`.
+pub fn draw_pass(
+    cpu_buffer: &mut CPUBuffer,
+    rect: &Rect,
+    camera: &Camera,
+    scene: &Scene,
+    renderer: &dyn Renderer,
+    scheduler: &TileScheduler,
+    pass: u32,
+) {
+    let (offset_x, offset_y) = pixel_sample_offset(pass, u32::MAX);
+    scheduler.render(cpu_buffer, rect, |x, y| {
+        let ray = pixel_ray(camera, rect, x as f32 + offset_x, y as f32 + offset_y);
+        renderer.shade(&ray, scene)
+    });
+}
+
+/// Camera ray through the point of the rect in pixels, the rect covers the whole viewport
+fn pixel_ray(camera: &Camera, rect: &Rect, x: f32, y: f32) -> Ray {
+    let view_x = 2. * x / rect.width as f32 - 1.;
+    let view_y = 1. - 2. * y / rect.height as f32;
+    camera.get_ray_in_viewport(view_x, view_y)
+}
+
+/// Offset of the sample inside of the pixel, the R2 low discrepancy sequence for several samples
+pub fn pixel_sample_offset(sample: u32, samples_per_pixel: u32) -> (f32, f32) {
+    // inverse powers of the plastic number
+    const ALPHA_X: f32 = 0.754_877_7;
+    const ALPHA_Y: f32 = 0.569_840_3;
+    if samples_per_pixel <= 1 {
+        return (0., 0.);
+    }
+    (
+        (0.5 + ALPHA_X * sample as f32).fract(),
+        (0.5 + ALPHA_Y * sample as f32).fract(),
+    )
+}
+
+/// Debug coloring by world position of the hit, maps [-1, 1] cube to [0, 1] colors
+pub fn position_color(point: Vector3<f32>) -> [f32; 3] {
+    [
+        (point.x + 1.) * 0.5,
+        (point.y + 1.) * 0.5,
+        (point.z + 1.) * 0.5,
+    ]
+}
@@ -0,0 +1,322 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RootMethod {
+    Bisection,
+    /// Regula falsi with Illinois modification, keeps the bracket
+    Secant,
+    /// Newton steps with numeric derivative, bisection when a step leaves the bracket
+    Newton,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Convergence {
+    Converged,
+    MaxIterations,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Root {
+    pub t: f32,
+    pub iterations: u32,
+    pub convergence: Convergence,
+}
+
+/// Interval of the ray with the values of the function on its ends,
+/// a bracket of a root when they have different signs
+#[derive(Debug, Clone, Copy)]
+pub struct Bracket {
+    pub t_start: f32,
+    pub f_start: f32,
+    pub t_end: f32,
+    pub f_end: f32,
+}
+
+/// Adaptive sampling of the whole interval looking for every sign change.
+///
+/// An interval without a sign change hides a pair of roots only if the function can reach zero
+/// inside it and come back, that is `|f_start| + |f_end| <= slope * length` for a bound `slope`
+/// of `|df/dt|`. The bound is the steepest slope between the initial samples times
+/// `slope_margin`, and at least the `min_slope` of the caller, so it follows the scale of `t`
+/// and of the function. A sampled slope is not a proof: a narrow spike between the initial
+/// samples may be steeper, more samples make it less likely.
+#[derive(Debug, Clone, Copy)]
+pub struct RootIsolation {
+    /// Uniform samples of the interval before subdivision
+    pub initial_samples: u32,
+    /// Max number of halvings of a sample interval
+    pub max_depth: u32,
+    /// Factor over the steepest sampled slope used as the bound of `|df/dt|`
+    pub slope_margin: f32,
+}
+
+impl Default for RootIsolation {
+    fn default() -> Self {
+        RootIsolation {
+            initial_samples: 8,
+            max_depth: 6,
+            slope_margin: 2.,
+        }
+    }
+}
+
+impl RootIsolation {
+    /// Intervals with a sign change inside of `ends`, sorted along the ray.
+    /// `min_slope` is the least bound of `|df/dt|`, see `RootIsolation`.
+    pub fn brackets<F>(&self, f: F, ends: Bracket, min_slope: f32) -> Vec<Bracket>
+    where
+        F: Fn(f32) -> f32,
+    {
+        let samples = self.initial_samples.max(1);
+        let mut points = Vec::with_capacity(samples as usize + 1);
+        points.push((ends.t_start, ends.f_start));
+        for sample in 1..samples {
+            let t = ends.t_start + (ends.t_end - ends.t_start) * sample as f32 / samples as f32;
+            points.push((t, f(t)));
+        }
+        points.push((ends.t_end, ends.f_end));
+
+        let steepest = points
+            .windows(2)
+            .map(|pair| ((pair[1].1 - pair[0].1) / (pair[1].0 - pair[0].0)).abs())
+            .filter(|slope| slope.is_finite())
+            .fold(0., f32::max);
+        let slope = (steepest * self.slope_margin).max(min_slope);
+
+        let mut brackets = Vec::new();
+        for pair in points.windows(2) {
+            let interval = Bracket {
+                t_start: pair[0].0,
+                f_start: pair[0].1,
+                t_end: pair[1].0,
+                f_end: pair[1].1,
+            };
+            self.subdivide(&f, interval, slope, 0, &mut brackets);
+        }
+        brackets
+    }
+
+    fn subdivide<F>(
+        &self,
+        f: &F,
+        interval: Bracket,
+        slope: f32,
+        depth: u32,
+        brackets: &mut Vec<Bracket>,
+    ) where
+        F: Fn(f32) -> f32,
+    {
+        let Bracket {
+            t_start: a,
+            f_start: f_a,
+            t_end: b,
+            f_end: f_b,
+        } = interval;
+        if f_a.is_nan() || f_b.is_nan() {
+            return;
+        }
+        if f_a.signum() != f_b.signum() {
+            brackets.push(interval);
+            return;
+        }
+        // a pair of roots fits only if the function can go to zero and back
+        let may_hide_roots = f_a.abs() + f_b.abs() <= slope * (b - a);
+        if depth < self.max_depth && may_hide_roots {
+            let middle = (a + b) * 0.5;
+            let f_middle = f(middle);
+            let first = Bracket {
+                t_end: middle,
+                f_end: f_middle,
+                ..interval
+            };
+            let second = Bracket {
+                t_start: middle,
+                f_start: f_middle,
+                ..interval
+            };
+            self.subdivide(f, first, slope, depth + 1, brackets);
+            self.subdivide(f, second, slope, depth + 1, brackets);
+        }
+    }
+}
+
+/// Policy for finding a root of a function along the ray
+#[derive(Debug, Clone, Copy)]
+pub struct RootSolver {
+    pub method: RootMethod,
+    /// Bisection steps looking for a sign change before the refinement
+    pub bracket_steps: u32,
+    /// Sample the whole interval instead of bracketing a single sign change,
+    /// finds the nearest root when there are several of them
+    pub isolation: Option<RootIsolation>,
+    /// Refinement stops when the bracket or the function value is smaller
+    pub tolerance: f32,
+    pub max_iterations: u32,
+}
+
+impl Default for RootSolver {
+    fn default() -> Self {
+        RootSolver {
+            method: RootMethod::Secant,
+            bracket_steps: 5,
+            isolation: None,
+            tolerance: 1e-5,
+            max_iterations: 20,
+        }
+    }
+}
+
+impl RootSolver {
+    /// Fixed 5 bracketing and 3 refinement bisection steps
+    pub fn legacy() -> RootSolver {
+        RootSolver {
+            method: RootMethod::Bisection,
+            bracket_steps: 5,
+            isolation: None,
+            tolerance: 0.,
+            max_iterations: 3,
+        }
+    }
+
+    pub fn with_method(self, method: RootMethod) -> RootSolver {
+        RootSolver { method, ..self }
+    }
+
+    pub fn with_isolation(self, isolation: RootIsolation) -> RootSolver {
+        RootSolver {
+            isolation: Some(isolation),
+            ..self
+        }
+    }
+
+    /// Refine root of `f` inside `[t_start, t_end]`, values on the ends must have different signs
+    pub fn refine<F>(&self, f: F, t_start: f32, f_start: f32, t_end: f32, f_end: f32) -> Root
+    where
+        F: Fn(f32) -> f32,
+    {
+        match self.method {
+            RootMethod::Bisection => self.bisection(f, t_start, f_start, t_end),
+            RootMethod::Secant => self.secant(f, t_start, f_start, t_end, f_end),
+            RootMethod::Newton => self.newton(f, t_start, f_start, t_end, f_end),
+        }
+    }
+
+    fn bisection<F>(&self, f: F, mut t_start: f32, mut f_start: f32, mut t_end: f32) -> Root
+    where
+        F: Fn(f32) -> f32,
+    {
+        for iteration in 0..self.max_iterations {
+            if t_end - t_start <= self.tolerance {
+                return self.converged((t_start + t_end) * 0.5, iteration);
+            }
+            let t_middle = (t_start + t_end) * 0.5;
+            let f_middle = f(t_middle);
+
+            if f_start.signum() != f_middle.signum() {
+                t_end = t_middle;
+            } else {
+                t_start = t_middle;
+                f_start = f_middle;
+            }
+        }
+        Root {
+            t: (t_start + t_end) * 0.5,
+            iterations: self.max_iterations,
+            convergence: if t_end - t_start <= self.tolerance {
+                Convergence::Converged
+            } else {
+                Convergence::MaxIterations
+            },
+        }
+    }
+
+    fn secant<F>(&self, f: F, t_start: f32, f_start: f32, t_end: f32, f_end: f32) -> Root
+    where
+        F: Fn(f32) -> f32,
+    {
+        let (mut a, mut f_a, mut b, mut f_b) = (t_start, f_start, t_end, f_end);
+        // side of the bracket moved on the previous step, -1 for `a`, 1 for `b`
+        let mut last_side = 0;
+        let mut t = (a + b) * 0.5;
+
+        for iteration in 1..=self.max_iterations {
+            t = (a * f_b - b * f_a) / (f_b - f_a);
+            if !(t > a && t < b) {
+                t = (a + b) * 0.5;
+            }
+            let f_t = f(t);
+
+            if f_t.signum() == f_a.signum() {
+                a = t;
+                f_a = f_t;
+                if last_side == -1 {
+                    f_b *= 0.5;
+                }
+                last_side = -1;
+            } else {
+                b = t;
+                f_b = f_t;
+                if last_side == 1 {
+                    f_a *= 0.5;
+                }
+                last_side = 1;
+            }
+
+            if f_t.abs() <= self.tolerance || b - a <= self.tolerance {
+                return self.converged(t, iteration);
+            }
+        }
+        self.not_converged(t)
+    }
+
+    fn newton<F>(&self, f: F, t_start: f32, f_start: f32, t_end: f32, f_end: f32) -> Root
+    where
+        F: Fn(f32) -> f32,
+    {
+        let (mut a, mut f_a, mut b) = (t_start, f_start, t_end);
+        let (mut t, mut f_t) = if f_start.abs() < f_end.abs() {
+            (t_start, f_start)
+        } else {
+            (t_end, f_end)
+        };
+
+        for iteration in 1..=self.max_iterations {
+            let delta = (self.tolerance * 0.5).max((b - a) * 1e-3).max(f32::EPSILON * t.abs());
+            let derivative = (f(t + delta) - f(t - delta)) / (2. * delta);
+
+            let mut next_t = t - f_t / derivative;
+            if !(next_t > a && next_t < b) {
+                next_t = (a + b) * 0.5;
+            }
+            let step = (next_t - t).abs();
+            t = next_t;
+            f_t = f(t);
+
+            if f_t.signum() == f_a.signum() {
+                a = t;
+                f_a = f_t;
+            } else {
+                b = t;
+            }
+
+            if f_t.abs() <= self.tolerance || step <= self.tolerance || b - a <= self.tolerance {
+                return self.converged(t, iteration);
+            }
+        }
+        self.not_converged(t)
+    }
+
+    fn converged(&self, t: f32, iterations: u32) -> Root {
+        Root {
+            t,
+            iterations,
+            convergence: Convergence::Converged,
+        }
+    }
+
+    fn not_converged(&self, t: f32) -> Root {
+        Root {
+            t,
+            iterations: self.max_iterations,
+            convergence: Convergence::MaxIterations,
+        }
+    }
+}
@@ -0,0 +1,241 @@
+use bvh::{
+    aabb::{Aabb, Bounded},
+    bounding_hierarchy::BHShape,
+    bvh::Bvh,
+};
+use cgmath::Vector3;
+
+use crate::{materials::Material, shapes::Shape};
+
+use super::{
+    hit::{Hit, Intersect},
+    light::Light,
+    mesh::{Mesh, MeshFace},
+    ray::Ray,
+};
+
+/// Geometry prepared for rendering: shapes, the triangulation of their curve patches
+/// in the world coords and optional BVH over both, built once instead of on every frame
+pub struct Scene {
+    pub shapes: Vec<Shape>,
+    pub triangulation: Mesh,
+    /// Index in `shapes` per face of `triangulation`
+    pub face_shapes: Vec<usize>,
+    pub lights: Vec<Light>,
+
+    shape_patches: Vec<ShapePatch>,
+    triangulation_faces: Vec<MeshFace>,
+    shape_bvh: Option<Bvh<f32, 3>>,
+    triangulation_bvh: Option<Bvh<f32, 3>>,
+}
+
+/// Curve patch of a shape with its box in the world coords, for the BVH over all shapes
+#[derive(Debug, Clone)]
+struct ShapePatch {
+    shape: usize,
+    patch: usize,
+    aabb: Aabb<f32, 3>,
+    bhv_node_index: usize,
+}
+
+impl Scene {
+    /// Patches of the shapes must be triangulated, see `CurveTriangle::triangulate`
+    pub fn new(shapes: Vec<Shape>, with_bvh: bool) -> Scene {
+        let mut triangulation = Mesh::default();
+        let mut face_shapes = Vec::new();
+        let mut shape_patches = Vec::new();
+        for (shape_id, shape) in shapes.iter().enumerate() {
+            let patch_offset = shape_patches.len();
+            let mesh = shape.transform.mesh(&Mesh::from_triangulations(&shape.patches));
+            triangulation.append(&mesh, patch_offset);
+            face_shapes.extend(std::iter::repeat_n(shape_id, mesh.indices.len()));
+
+            for (patch_id, patch) in shape.patches.iter().enumerate() {
+                // box of the transformed corners of the local box
+                let local = patch.aabb();
+                let mut aabb = Aabb::empty();
+                for corner in 0..8 {
+                    let point = shape.transform.point(Vector3::new(
+                        if corner & 1 == 0 { local.min.x } else { local.max.x },
+                        if corner & 2 == 0 { local.min.y } else { local.max.y },
+                        if corner & 4 == 0 { local.min.z } else { local.max.z },
+                    ));
+                    aabb = aabb.grow(&nalgebra::Point3::new(point.x, point.y, point.z));
+                }
+                shape_patches.push(ShapePatch {
+                    shape: shape_id,
+                    patch: patch_id,
+                    aabb,
+                    bhv_node_index: 0,
+                });
+            }
+        }
+        let triangulation_faces = triangulation.faces();
+
+        let mut scene = Scene {
+            shapes,
+            triangulation,
+            face_shapes,
+            lights: Vec::new(),
+            shape_patches,
+            triangulation_faces,
+            shape_bvh: None,
+            triangulation_bvh: None,
+        };
+        if with_bvh {
+            scene.build_bvh();
+        }
+        scene
+    }
+
+    /// Build BVH over the curve patches and the triangulation, shapes must not move after it
+    pub fn build_bvh(&mut self) {
+        self.shape_bvh = Some(Bvh::build(&mut self.shape_patches));
+        self.triangulation_bvh = Some(Bvh::build(&mut self.triangulation_faces));
+    }
+
+    /// Whether `build_bvh` was called, hits are searched in the trees
+    pub fn has_bvh(&self) -> bool {
+        self.shape_bvh.is_some()
+    }
+
+    /// Nearest hit of triangulation, `primitive_id` of the hit is the face of the mesh
+    pub fn nearest_triangulation_hit(&self, ray: &Ray) -> Option<Hit> {
+        nearest_hit_by(
+            &self.triangulation_faces,
+            self.triangulation_bvh.as_ref(),
+            ray,
+            |face| {
+                self.triangulation.intersect_face(face.face, ray).map(|mut hit| {
+                    hit.shape_id = self.face_shapes[face.face];
+                    hit
+                })
+            },
+        )
+    }
+
+    /// Nearest hit of curve surfaces, `primitive_id` of the hit is the patch
+    /// counting the patches of all shapes in order
+    pub fn nearest_curve_hit(&self, ray: &Ray) -> Option<Hit> {
+        nearest_hit_by(&self.shape_patches, self.shape_bvh.as_ref(), ray, |shape_patch| {
+            let shape = &self.shapes[shape_patch.shape];
+            let local_ray = shape.transform.ray_to_local(ray);
+            shape.patches[shape_patch.patch].intersect(&local_ray).map(|hit| {
+                let mut hit = shape.transform.hit_to_world(hit, ray);
+                hit.shape_id = shape_patch.shape;
+                hit
+            })
+        })
+    }
+
+    /// Whether curve surfaces hide anything along the ray closer than `t_max`,
+    /// stops at the first found surface, see `CurveTriangle::occludes`
+    pub fn occluded(&self, ray: &Ray, t_max: f32) -> bool {
+        any_hit_by(&self.shape_patches, self.shape_bvh.as_ref(), ray, |shape_patch| {
+            let shape = &self.shapes[shape_patch.shape];
+            // distances along the local ray are shorter by the scale
+            shape.patches[shape_patch.patch]
+                .occludes(&shape.transform.ray_to_local(ray), t_max / shape.transform.scale)
+        })
+    }
+
+    /// Same as `occluded` for the triangulation
+    pub fn triangulation_occluded(&self, ray: &Ray, t_max: f32) -> bool {
+        any_hit_by(
+            &self.triangulation_faces,
+            self.triangulation_bvh.as_ref(),
+            ray,
+            |face| match self.triangulation.face(face.face).intersect_with_bary(ray) {
+                Ok((t, _)) => t > 0. && t < t_max,
+                Err(_) => false,
+            },
+        )
+    }
+
+    /// Material of the shape that was hit
+    pub fn hit_material(&self, hit: &Hit) -> Material {
+        self.shapes
+            .get(hit.shape_id)
+            .map(|shape| shape.material)
+            .unwrap_or_default()
+    }
+}
+
+/// Nearest hit of the primitives, `primitive_id` of the hit is the index in `primitives`.
+/// `bvh` must be built over the same primitives.
+pub fn nearest_hit<T>(primitives: &[T], bvh: Option<&Bvh<f32, 3>>, ray: &Ray) -> Option<Hit>
+where
+    T: Intersect + BHShape<f32, 3>,
+{
+    nearest_hit_by(primitives, bvh, ray, |primitive| primitive.intersect(ray))
+}
+
+/// Same as `nearest_hit` for primitives which need context to be intersected
+pub fn nearest_hit_by<T, F>(
+    primitives: &[T],
+    bvh: Option<&Bvh<f32, 3>>,
+    ray: &Ray,
+    intersect: F,
+) -> Option<Hit>
+where
+    T: BHShape<f32, 3>,
+    F: Fn(&T) -> Option<Hit>,
+{
+    let mut nearest: Option<Hit> = None;
+    let mut check = |primitive_id: usize, primitive: &T| {
+        if let Some(mut hit) = intersect(primitive) {
+            if nearest.is_none_or(|nearest| hit.t < nearest.t) {
+                hit.primitive_id = primitive_id;
+                nearest = Some(hit);
+            }
+        }
+    };
+
+    match bvh {
+        Some(bvh) => {
+            for primitive in bvh.traverse(&ray.bvh_ray(), primitives) {
+                check(index_in(primitives, primitive), primitive);
+            }
+        }
+        None => {
+            for (primitive_id, primitive) in primitives.iter().enumerate() {
+                check(primitive_id, primitive);
+            }
+        }
+    }
+    nearest
+}
+
+/// Whether any of the primitives passes the test, stops at the first one.
+/// `bvh` must be built over the same primitives.
+pub fn any_hit_by<T, F>(primitives: &[T], bvh: Option<&Bvh<f32, 3>>, ray: &Ray, test: F) -> bool
+where
+    T: BHShape<f32, 3>,
+    F: Fn(&T) -> bool,
+{
+    match bvh {
+        Some(bvh) => bvh.traverse_iterator(&ray.bvh_ray(), primitives).any(test),
+        None => primitives.iter().any(test),
+    }
+}
+
+/// Index of an element borrowed from the slice
+fn index_in<T>(slice: &[T], element: &T) -> usize {
+    (element as *const T as usize - slice.as_ptr() as usize) / std::mem::size_of::<T>()
+}
+
+impl Bounded<f32, 3> for ShapePatch {
+    fn aabb(&self) -> Aabb<f32, 3> {
+        self.aabb
+    }
+}
+
+impl BHShape<f32, 3> for ShapePatch {
+    fn set_bh_node_index(&mut self, index: usize) {
+        self.bhv_node_index = index
+    }
+
+    fn bh_node_index(&self) -> usize {
+        self.bhv_node_index
+    }
+}
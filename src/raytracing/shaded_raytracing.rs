@@ -0,0 +1,18 @@
+use crate::utils::Rng;
+
+use super::{ray::Ray, renderer::Renderer, scene::Scene, shading::direct_lighting};
+
+/// Intersects curve surfaces of the patches and lights them by the materials of the shapes
+pub struct ShadedRenderer;
+
+impl Renderer for ShadedRenderer {
+    fn shade(&self, ray: &Ray, scene: &Scene) -> [f32; 3] {
+        match scene.nearest_curve_hit(ray) {
+            Some(hit) => {
+                let mut rng = Rng::from_ray(ray);
+                direct_lighting(scene, ray, &hit, &scene.hit_material(&hit), &mut rng)
+            }
+            None => [0., 0., 0.],
+        }
+    }
+}
@@ -0,0 +1,103 @@
+use cgmath::{InnerSpace, Vector3};
+
+use crate::{
+    materials::{Material, RGB, WHITE},
+    utils::Rng,
+};
+
+use super::{hit::Hit, light::Light, ray::Ray, scene::Scene};
+
+/// Light every surface gets without a light source, part of the material color
+const AMBIENT: f32 = 0.05;
+
+/// Shadow rays start this far from the surface along the normal, so they don't hit it again
+pub const SHADOW_BIAS: f32 = 1e-3;
+
+/// Random points of an area light per shaded hit
+pub const AREA_LIGHT_SAMPLES: u32 = 16;
+
+/// Color of the hit lit directly by the lights of the scene: Lambert diffuse
+/// and Blinn-Phong specular weighted by `Material::diffusion_koefficient`, lights hidden
+/// by curve surfaces cast shadows. Scenes without lights are lit from the camera. Area lights are averaged over
+/// `AREA_LIGHT_SAMPLES` points.
+pub fn direct_lighting(
+    scene: &Scene,
+    ray: &Ray,
+    hit: &Hit,
+    material: &Material,
+    rng: &mut Rng,
+) -> RGB {
+    let headlight = headlight(ray);
+    let lights: &[Light] = if scene.lights.is_empty() {
+        &headlight
+    } else {
+        &scene.lights
+    };
+    let mut color = material.color.map(|channel| channel * AMBIENT);
+    let reflected = reflected_light(scene, lights, ray, hit, material, AREA_LIGHT_SAMPLES, rng);
+    for (channel, light) in color.iter_mut().zip(reflected) {
+        *channel += light;
+    }
+    color
+}
+
+/// White directional light along the camera ray, lights scenes without lights
+pub fn headlight(ray: &Ray) -> [Light; 1] {
+    [Light::Directional {
+        direction: ray.direction.normalize(),
+        color: WHITE,
+        intensity: 1.,
+    }]
+}
+
+/// Light of the `lights` reflected by the hit back along the ray, without the ambient part.
+/// Area lights are averaged over `area_samples` points.
+pub fn reflected_light(
+    scene: &Scene,
+    lights: &[Light],
+    ray: &Ray,
+    hit: &Hit,
+    material: &Material,
+    area_samples: u32,
+    rng: &mut Rng,
+) -> RGB {
+    let to_viewer = -ray.direction.normalize();
+    let diffuse = material.diffusion_koefficient.clamp(0., 1.);
+    let specular = material.specular_koefficient();
+    let mut color = [0.; 3];
+    for light in lights.iter() {
+        let samples = if light.is_delta() {
+            1
+        } else {
+            area_samples.max(1)
+        };
+        for _ in 0..samples {
+            let sample = match light.sample(hit.point, rng) {
+                Some(sample) => sample,
+                None => continue,
+            };
+            let lambert = hit.normal.dot(sample.direction);
+            if lambert <= 0. || in_shadow(scene, hit, sample.direction, sample.distance) {
+                continue;
+            }
+            let half_vector = (sample.direction + to_viewer).normalize();
+            let highlight = hit.normal.dot(half_vector).max(0.).powf(material.shininess);
+            for ((channel, radiance), albedo) in
+                color.iter_mut().zip(sample.radiance).zip(material.color)
+            {
+                *channel +=
+                    radiance * (diffuse * lambert * albedo + specular * highlight) / samples as f32;
+            }
+        }
+    }
+    color
+}
+
+/// Whether curve surfaces hide the light from the hit point
+pub fn in_shadow(scene: &Scene, hit: &Hit, to_light: Vector3<f32>, distance: f32) -> bool {
+    let shadow_ray = Ray {
+        origin: hit.point + hit.normal * SHADOW_BIAS,
+        direction: to_light,
+    };
+    scene.occluded(&shadow_ray, distance - SHADOW_BIAS)
+}
@@ -0,0 +1,123 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc,
+    },
+    thread,
+};
+
+use crate::cpu_buffer::{CPUBuffer, Rect};
+
+/// Splits a rect into square tiles and renders them on a pool of threads
+#[derive(Debug, Clone, Copy)]
+pub struct TileScheduler {
+    pub tile_size: u32,
+    pub threads: usize,
+}
+
+impl Default for TileScheduler {
+    fn default() -> Self {
+        let threads = thread::available_parallelism()
+            .map(|count| count.get())
+            .unwrap_or(1);
+        TileScheduler {
+            tile_size: 32,
+            threads,
+        }
+    }
+}
+
+impl TileScheduler {
+    pub fn new(tile_size: u32, threads: usize) -> TileScheduler {
+        TileScheduler {
+            tile_size: tile_size.max(1),
+            threads: threads.max(1),
+        }
+    }
+
+    /// Single threaded scheduler, renders tiles in order on the caller thread
+    pub fn serial() -> TileScheduler {
+        TileScheduler::new(32, 1)
+    }
+
+    /// Tiles covering the rect, coords are relative to the rect
+    pub fn tiles(&self, rect: &Rect) -> Vec<Rect> {
+        let tile_size = self.tile_size.max(1);
+        let mut tiles = Vec::new();
+        for bottom in (0..rect.height).step_by(tile_size as usize) {
+            for left in (0..rect.width).step_by(tile_size as usize) {
+                tiles.push(Rect {
+                    left,
+                    bottom,
+                    width: tile_size.min(rect.width - left),
+                    height: tile_size.min(rect.height - bottom),
+                });
+            }
+        }
+        tiles
+    }
+
+    /// Fill the rect of the buffer with `shade_pixel(x, y)`,
+    /// where `x` and `y` are relative to the rect.
+    /// The result does not depend on tile size and thread count.
+    pub fn render<F>(&self, cpu_buffer: &mut CPUBuffer, rect: &Rect, shade_pixel: F)
+    where
+        F: Fn(u32, u32) -> [f32; 3] + Sync,
+    {
+        let tiles = self.tiles(rect);
+
+        if self.threads <= 1 || tiles.len() <= 1 {
+            for tile in tiles.iter() {
+                let pixels = render_tile(tile, &shade_pixel);
+                write_tile(cpu_buffer, rect, tile, &pixels);
+            }
+            return;
+        }
+
+        let next_tile = AtomicUsize::new(0);
+        let (sender, receiver) = mpsc::channel();
+        thread::scope(|scope| {
+            for _ in 0..self.threads.min(tiles.len()) {
+                let sender = sender.clone();
+                let (tiles, next_tile, shade_pixel) = (&tiles, &next_tile, &shade_pixel);
+                scope.spawn(move || loop {
+                    let tile_index = next_tile.fetch_add(1, Ordering::Relaxed);
+                    if tile_index >= tiles.len() {
+                        break;
+                    }
+                    let pixels = render_tile(&tiles[tile_index], shade_pixel);
+                    if sender.send((tile_index, pixels)).is_err() {
+                        break;
+                    }
+                });
+            }
+            // workers hold the only senders left, so the loop ends with the last tile
+            drop(sender);
+
+            for (tile_index, pixels) in receiver.iter() {
+                write_tile(cpu_buffer, rect, &tiles[tile_index], &pixels);
+            }
+        });
+    }
+}
+
+fn render_tile<F>(tile: &Rect, shade_pixel: &F) -> Vec<[f32; 3]>
+where
+    F: Fn(u32, u32) -> [f32; 3],
+{
+    let mut pixels = Vec::with_capacity((tile.width * tile.height) as usize);
+    for y in tile.bottom..tile.bottom + tile.height {
+        for x in tile.left..tile.left + tile.width {
+            pixels.push(shade_pixel(x, y));
+        }
+    }
+    pixels
+}
+
+fn write_tile(cpu_buffer: &mut CPUBuffer, rect: &Rect, tile: &Rect, pixels: &[[f32; 3]]) {
+    for (index, pixel) in pixels.iter().enumerate() {
+        let x = rect.left + tile.left + index as u32 % tile.width;
+        let y = rect.bottom + tile.bottom + index as u32 / tile.width;
+        cpu_buffer[(x as usize, y as usize)] = *pixel;
+    }
+}
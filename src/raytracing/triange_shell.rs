@@ -1,4 +1,8 @@
-use super::{ray::Ray, triangle::Triangle};
+use super::{
+    hit::{Hit, Intersect},
+    ray::Ray,
+    triangle::Triangle,
+};
 
 #[derive(Debug)]
 pub struct TriangleShell<const N: usize> {
@@ -6,11 +10,12 @@ pub struct TriangleShell<const N: usize> {
 }
 
 impl<const N: usize> TriangleShell<N> {
-    pub fn get_slice_for_ray(&self, ray: &Ray) -> (f32, f32) {
+    /// Part of the ray inside the shell, `None` if the ray misses it
+    pub fn get_slice_for_ray(&self, ray: &Ray) -> Option<(f32, f32)> {
         let (mut t_start, mut t_end) = (f32::INFINITY, -1.);
 
         for triangle in self.triangles.iter() {
-            if let Ok((t, _)) = triangle.intersect(ray) {
+            if let Ok((t, _)) = triangle.intersect_with_bary(ray) {
                 t_start = t.min(t_start);
                 t_end = t.max(t_end);
             }
@@ -18,9 +23,25 @@ impl<const N: usize> TriangleShell<N> {
 
         // no intersection
         if t_start > t_end || t_end < 0.0 {
-            return (-1.0, -1.0);
+            return None;
         }
 
-        (t_start.max(0.), t_end)
+        Some((t_start.max(0.), t_end))
+    }
+}
+
+impl<const N: usize> Intersect for TriangleShell<N> {
+    /// Nearest wall of the shell
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        let mut nearest: Option<Hit> = None;
+        for (index, triangle) in self.triangles.iter().enumerate() {
+            if let Some(mut hit) = triangle.intersect(ray) {
+                if nearest.is_none_or(|nearest| hit.t < nearest.t) {
+                    hit.primitive_id = index;
+                    nearest = Some(hit);
+                }
+            }
+        }
+        nearest
     }
 }
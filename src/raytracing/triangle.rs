@@ -8,7 +8,10 @@ use cgmath::{InnerSpace, Matrix, Matrix3, Zero, Vector3};
 
 use crate::utils::MinMaxIterExt;
 
-use super::ray::Ray;
+use super::{
+    hit::{Hit, Intersect},
+    ray::Ray,
+};
 
 #[derive(Debug, Clone)]
 pub struct Triangle {
@@ -63,8 +66,16 @@ impl Triangle {
         return t;
     }
 
-    /// Return coords of interseption + barycentric coords or Nothing
-    pub fn intersect(&self, ray: &Ray) -> Result<(f32, Vector3<f32>), Vector3<f32>> {
+    pub fn normal(&self) -> Vector3<f32> {
+        (self.vertexes[1] - self.vertexes[0])
+            .cross(self.vertexes[2] - self.vertexes[0])
+            .normalize()
+    }
+
+    /// Return coords of interseption + barycentric coords,
+    /// or barycentric coords of the plane intersection outside of the triangle
+    #[inline]
+    pub fn intersect_with_bary(&self, ray: &Ray) -> Result<(f32, Vector3<f32>), Vector3<f32>> {
         // solve from https://en.wikipedia.org/wiki/Line%E2%80%93plane_intersection
         let e1 = self.vertexes[1] - self.vertexes[0];
         let e2 = self.vertexes[2] - self.vertexes[0];
@@ -91,6 +102,15 @@ impl Triangle {
     }
 }
 
+impl Intersect for Triangle {
+    fn intersect(&self, ray: &Ray) -> Option<Hit> {
+        match self.intersect_with_bary(ray) {
+            Ok((t, bary)) if t > 0. => Some(Hit::new(ray, t, self.normal(), bary)),
+            _ => None,
+        }
+    }
+}
+
 impl Bounded<f32, 3> for Triangle {
     fn aabb(&self) -> Aabb<f32, 3> {
         let (min_x, max_x) = self.vertexes.iter().map(|&v| v[0]).min_max();
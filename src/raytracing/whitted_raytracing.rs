@@ -0,0 +1,134 @@
+use cgmath::{InnerSpace, Vector3};
+
+use crate::{materials::RGB, utils::Rng};
+
+use super::{
+    hit::Hit,
+    ray::Ray,
+    renderer::Renderer,
+    scene::Scene,
+    shading::{direct_lighting, SHADOW_BIAS},
+};
+
+/// Whitted ray tracer: the shaded color of the opaque part of the material, plus mirror
+/// reflection and refraction traced recursively up to `max_depth` bounces.
+///
+/// `Material::alpha` is the opaque part, it is lit directly and reflects `specular_koefficient`
+/// of the light like a mirror. The rest of the light passes the surface, split by the Fresnel
+/// term (Schlick) between the reflected and the refracted ray, `refraction_koefficient` is
+/// the index of refraction of the material inside of the shape, outside is vacuum.
+pub struct WhittedRenderer {
+    /// Bounces after the camera ray, 0 is the shaded renderer
+    pub max_depth: u32,
+}
+
+/// Rays carrying less of the pixel color are not traced
+const MIN_WEIGHT: f32 = 1e-3;
+
+impl Renderer for WhittedRenderer {
+    fn shade(&self, ray: &Ray, scene: &Scene) -> [f32; 3] {
+        let mut rng = Rng::from_ray(ray);
+        self.trace(ray, scene, 0, 1., &mut rng)
+    }
+}
+
+impl WhittedRenderer {
+    /// Color coming along the ray, `weight` is its part in the pixel color
+    fn trace(&self, ray: &Ray, scene: &Scene, depth: u32, weight: f32, rng: &mut Rng) -> RGB {
+        let hit = match scene.nearest_curve_hit(ray) {
+            Some(hit) => hit,
+            None => return [0., 0., 0.],
+        };
+        let material = scene.hit_material(&hit);
+        let opacity = material.alpha.clamp(0., 1.);
+        let mut color = [0.; 3];
+
+        if opacity > 0. {
+            let direct = direct_lighting(scene, ray, &hit, &material, rng);
+            for channel in 0..3 {
+                color[channel] += opacity * direct[channel];
+            }
+        }
+        if depth >= self.max_depth {
+            return color;
+        }
+
+        let direction = ray.direction.normalize();
+        let transparency = 1. - opacity;
+        // index of refraction behind the surface over the one in front of it
+        let ior = material.refraction_koefficient.max(f32::EPSILON);
+        let relative_ior = if hit.front_face { ior } else { 1. / ior };
+        let refracted = refract(direction, hit.normal, relative_ior);
+        let fresnel = match refracted {
+            Some(refracted) => schlick(direction, refracted, hit.normal, relative_ior),
+            // total internal reflection
+            None => 1.,
+        };
+
+        let reflectance = opacity * material.specular_koefficient() + transparency * fresnel;
+        if weight * reflectance > MIN_WEIGHT {
+            let reflected = reflect_ray(&hit, direction);
+            let reflected = self.trace(&reflected, scene, depth + 1, weight * reflectance, rng);
+            for channel in 0..3 {
+                color[channel] += reflectance * reflected[channel];
+            }
+        }
+
+        let transmittance = transparency * (1. - fresnel);
+        if let Some(refracted) = refracted.filter(|_| weight * transmittance > MIN_WEIGHT) {
+            let refracted = Ray {
+                origin: hit.point - hit.normal * SHADOW_BIAS,
+                direction: refracted,
+            };
+            let transmitted = self.trace(&refracted, scene, depth + 1, weight * transmittance, rng);
+            // the material color filters the light passing it
+            for channel in 0..3 {
+                color[channel] += transmittance * material.color[channel] * transmitted[channel];
+            }
+        }
+        color
+    }
+}
+
+/// Mirror ray from the hit, started off the surface on the side of the incoming ray
+fn reflect_ray(hit: &Hit, direction: Vector3<f32>) -> Ray {
+    Ray {
+        origin: hit.point + hit.normal * SHADOW_BIAS,
+        direction: direction - hit.normal * 2. * direction.dot(hit.normal),
+    }
+}
+
+/// Unit direction refracted by Snell's law, `None` for total internal reflection.
+/// `normal` faces the unit `direction`, `relative_ior` is the index of refraction behind
+/// the surface over the one in front of it.
+pub fn refract(
+    direction: Vector3<f32>,
+    normal: Vector3<f32>,
+    relative_ior: f32,
+) -> Option<Vector3<f32>> {
+    let eta = 1. / relative_ior;
+    let cos_incident = -direction.dot(normal);
+    let cos_refracted_squared = 1. - eta * eta * (1. - cos_incident * cos_incident);
+    if cos_refracted_squared < 0. {
+        return None;
+    }
+    let refracted = direction * eta + normal * (eta * cos_incident - cos_refracted_squared.sqrt());
+    Some(refracted.normalize())
+}
+
+/// Schlick's approximation of the reflected part of the light,
+/// by the angle on the optically thinner side of the surface
+pub fn schlick(
+    direction: Vector3<f32>,
+    refracted: Vector3<f32>,
+    normal: Vector3<f32>,
+    relative_ior: f32,
+) -> f32 {
+    let r0 = ((relative_ior - 1.) / (relative_ior + 1.)).powi(2);
+    let cosine = if relative_ior >= 1. {
+        -direction.dot(normal)
+    } else {
+        refracted.dot(normal).abs()
+    };
+    r0 + (1. - r0) * (1. - cosine.clamp(0., 1.)).powi(5)
+}
@@ -0,0 +1,528 @@
+use std::{
+    collections::HashMap,
+    fs,
+    io::{Error as IoError, ErrorKind, Result as IoResult},
+    path::Path,
+};
+
+use cgmath::{Deg, InnerSpace, Matrix3, Rad, SquareMatrix, Vector3};
+use serde::Deserialize;
+
+use crate::{
+    materials::{Material, RGB, WHITE},
+    raytracing::{
+        camera::Camera,
+        curve_triangle::CurveTriangle,
+        light::{AreaLight, Light},
+        renderer::{RendererKind, DEFAULT_TRACE_DEPTH},
+        scene::Scene,
+        triangle::Triangle,
+    },
+    shapes::{self, Shape, Transform},
+};
+
+/// Everything needed to render a scene described by a JSON file, see `read_scene_file`
+pub struct SceneFile {
+    pub width: u32,
+    pub height: u32,
+    /// Ratio of the camera is set for the strip of every renderer
+    pub camera: Camera,
+    pub renderers: Vec<RendererKind>,
+    /// Bounces of the reflected and refracted rays, see `RendererKind::renderer`
+    pub trace_depth: u32,
+    pub scene: Scene,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SceneDescription {
+    #[serde(default)]
+    image: ImageDescription,
+    camera: CameraDescription,
+    #[serde(default = "default_renderers")]
+    renderers: Vec<String>,
+    #[serde(default = "default_trace_depth")]
+    trace_depth: u32,
+    /// Accuracy of `CurveTriangle::triangulate`
+    #[serde(default = "default_triangulation")]
+    triangulation: i32,
+    #[serde(default)]
+    bvh: bool,
+    #[serde(default)]
+    lights: Vec<LightDescription>,
+    #[serde(default)]
+    materials: HashMap<String, MaterialDescription>,
+    objects: Vec<ObjectDescription>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ImageDescription {
+    width: u32,
+    height: u32,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CameraDescription {
+    origin: [f32; 3],
+    /// Either the direction or the point to look at
+    direction: Option<[f32; 3]>,
+    look_at: Option<[f32; 3]>,
+    /// Vertical field of view, degrees
+    #[serde(default = "default_fov")]
+    fov: f32,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum LightDescription {
+    Point {
+        position: [f32; 3],
+        #[serde(default = "default_color")]
+        color: RGB,
+        #[serde(default = "default_intensity")]
+        intensity: f32,
+    },
+    Directional {
+        direction: [f32; 3],
+        #[serde(default = "default_color")]
+        color: RGB,
+        #[serde(default = "default_intensity")]
+        intensity: f32,
+    },
+    /// Emissive triangles and curve patches, see `AreaLight`
+    Area {
+        #[serde(default)]
+        triangles: Vec<[[f32; 3]; 3]>,
+        #[serde(default)]
+        curve_triangles: Vec<CurvePatchDescription>,
+        #[serde(default = "default_color")]
+        color: RGB,
+        #[serde(default = "default_intensity")]
+        intensity: f32,
+    },
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct CurvePatchDescription {
+    vertexes: [[f32; 3]; 3],
+    pivots: [[f32; 3]; 3],
+    #[serde(default = "default_koefs")]
+    koefs: [f32; 3],
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct MaterialDescription {
+    #[serde(default = "default_color")]
+    color: RGB,
+    #[serde(default = "default_one")]
+    alpha: f32,
+    #[serde(default = "default_one")]
+    refraction_koefficient: f32,
+    #[serde(default = "default_one")]
+    diffusion_koefficient: f32,
+    #[serde(default = "default_shininess")]
+    shininess: f32,
+}
+
+/// Objects with an optional `transform`, so one primitive or mesh can be placed many times
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+enum ObjectDescription {
+    /// Flat triangle
+    Triangle {
+        vertexes: [[f32; 3]; 3],
+        material: Option<String>,
+        #[serde(default)]
+        transform: TransformDescription,
+    },
+    CurveTriangle {
+        vertexes: [[f32; 3]; 3],
+        pivots: [[f32; 3]; 3],
+        #[serde(default = "default_koefs")]
+        koefs: [f32; 3],
+        material: Option<String>,
+        #[serde(default)]
+        transform: TransformDescription,
+    },
+    /// Octants of `get_curve_sphere` scaled by the radius and moved to the center
+    CurveSphere {
+        #[serde(default)]
+        center: [f32; 3],
+        #[serde(default = "default_one")]
+        radius: f32,
+        material: Option<String>,
+        #[serde(default)]
+        transform: TransformDescription,
+    },
+    /// OBJ or ASCII PLY mesh, see `shapes::load_curve_mesh`, the path is relative to the scene file
+    Mesh {
+        path: String,
+        material: Option<String>,
+        #[serde(default)]
+        transform: TransformDescription,
+    },
+}
+
+impl ObjectDescription {
+    fn transform(&self) -> &TransformDescription {
+        match self {
+            ObjectDescription::Triangle { transform, .. }
+            | ObjectDescription::CurveTriangle { transform, .. }
+            | ObjectDescription::CurveSphere { transform, .. }
+            | ObjectDescription::Mesh { transform, .. } => transform,
+        }
+    }
+}
+
+/// Scale, then rotation around x, y and z by degrees, then translation.
+/// Goes after the placement of the object itself, like the center and radius of `curve_sphere`.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TransformDescription {
+    #[serde(default)]
+    translation: [f32; 3],
+    #[serde(default)]
+    rotation: [f32; 3],
+    #[serde(default = "default_one")]
+    scale: f32,
+}
+
+impl Default for TransformDescription {
+    fn default() -> Self {
+        TransformDescription {
+            translation: [0.; 3],
+            rotation: [0.; 3],
+            scale: 1.,
+        }
+    }
+}
+
+impl Default for ImageDescription {
+    fn default() -> Self {
+        ImageDescription {
+            width: 1000,
+            height: 500,
+        }
+    }
+}
+
+fn default_renderers() -> Vec<String> {
+    vec![
+        RendererKind::Triangulated.name().to_string(),
+        RendererKind::Trihedral.name().to_string(),
+    ]
+}
+
+fn default_trace_depth() -> u32 {
+    DEFAULT_TRACE_DEPTH
+}
+
+fn default_triangulation() -> i32 {
+    5
+}
+
+fn default_fov() -> f32 {
+    90.
+}
+
+fn default_color() -> RGB {
+    WHITE
+}
+
+fn default_intensity() -> f32 {
+    1.
+}
+
+fn default_one() -> f32 {
+    1.
+}
+
+fn default_shininess() -> f32 {
+    Material::default().shininess
+}
+
+fn default_koefs() -> [f32; 3] {
+    [2., 2., 2.]
+}
+
+/// Read a JSON scene, paths of meshes are relative to `base_dir`.
+/// Errors start with the field like `objects[2].material`, syntax and type errors end with the line and column,
+/// inside of lights and objects they point to the object.
+pub fn read_scene_file(text: &str, base_dir: &Path) -> IoResult<SceneFile> {
+    let mut deserializer = serde_json::Deserializer::from_str(text);
+    let description: SceneDescription = serde_path_to_error::deserialize(&mut deserializer)
+        .map_err(|error| {
+            let field = error.path().to_string();
+            // messages of serde_json end with the line and column
+            let message = error.into_inner().to_string();
+            if field == "." {
+                IoError::new(ErrorKind::InvalidData, message)
+            } else {
+                invalid_field(&field, &message)
+            }
+        })?;
+    deserializer
+        .end()
+        .map_err(|error| IoError::new(ErrorKind::InvalidData, error.to_string()))?;
+
+    let image = &description.image;
+    if image.width == 0 || image.height == 0 {
+        return Err(invalid_field("image", "width and height must be positive"));
+    }
+
+    let renderers = description
+        .renderers
+        .iter()
+        .enumerate()
+        .map(|(index, name)| {
+            name.parse()
+                .map_err(|error: String| invalid_field(&format!("renderers[{}]", index), &error))
+        })
+        .collect::<IoResult<Vec<RendererKind>>>()?;
+    if renderers.is_empty() {
+        return Err(invalid_field("renderers", "expected at least one renderer"));
+    }
+
+    let camera = camera(&description.camera, image, renderers.len())?;
+
+    let find_material = |name: &Option<String>, field: &str| -> IoResult<Material> {
+        match name {
+            Some(name) => match description.materials.get(name) {
+                Some(material) => Ok(Material {
+                    color: material.color,
+                    alpha: material.alpha,
+                    refraction_koefficient: material.refraction_koefficient,
+                    diffusion_koefficient: material.diffusion_koefficient,
+                    shininess: material.shininess,
+                }),
+                None => Err(invalid_field(
+                    field,
+                    &format!("unknown material `{}`", name),
+                )),
+            },
+            None => Ok(Material::default()),
+        }
+    };
+
+    let lights = description
+        .lights
+        .iter()
+        .enumerate()
+        .map(|(index, light)| {
+            let field = |name: &str| format!("lights[{}].{}", index, name);
+            Ok(match light {
+                &LightDescription::Point {
+                    position,
+                    color,
+                    intensity,
+                } => Light::Point {
+                    position: position.into(),
+                    color,
+                    intensity,
+                },
+                &LightDescription::Directional {
+                    direction,
+                    color,
+                    intensity,
+                } => Light::Directional {
+                    direction: unit(direction, &field("direction"))?,
+                    color,
+                    intensity,
+                },
+                LightDescription::Area {
+                    triangles,
+                    curve_triangles,
+                    color,
+                    intensity,
+                } => {
+                    let mut patches = Vec::new();
+                    for vertexes in triangles.iter() {
+                        let triangle = Triangle::new(vertexes.map(Vector3::from));
+                        // the normal of a degenerate triangle is NaN
+                        if triangle.normal().magnitude2().is_nan() {
+                            return Err(invalid_field(&field("triangles"), "degenerate triangle"));
+                        }
+                        // triangulated into the triangle itself
+                        let mut patch = shapes::get_flat_patch(triangle);
+                        patch.triangulate(1);
+                        patches.push(patch);
+                    }
+                    for patch in curve_triangles.iter() {
+                        if patch.koefs.iter().any(|&koef| koef.is_nan() || koef <= 0.) {
+                            return Err(invalid_field(
+                                &field("curve_triangles"),
+                                "koefs must be positive",
+                            ));
+                        }
+                        let mut patch = CurveTriangle::new(
+                            Triangle::new(patch.vertexes.map(Vector3::from)),
+                            patch.pivots.map(Vector3::from),
+                            patch.koefs,
+                        );
+                        patch.triangulate(description.triangulation.max(1));
+                        patches.push(patch);
+                    }
+                    let area = AreaLight::from_patches(&patches, *color, *intensity);
+                    if area.area().is_nan() || area.area() <= 0. {
+                        return Err(invalid_field(
+                            &field("triangles"),
+                            "expected triangles or curve_triangles with some area",
+                        ));
+                    }
+                    Light::Area(area)
+                }
+            })
+        })
+        .collect::<IoResult<Vec<Light>>>()?;
+
+    let mut shapes = Vec::new();
+    for (index, object) in description.objects.iter().enumerate() {
+        let field = |name: &str| format!("objects[{}].{}", index, name);
+        let (patches, transform, material_name) = match object {
+            ObjectDescription::Triangle {
+                vertexes, material, ..
+            } => {
+                let triangle = Triangle::new(vertexes.map(Vector3::from));
+                // the normal of a degenerate triangle is NaN
+                if triangle.normal().magnitude2().is_nan() {
+                    return Err(invalid_field(&field("vertexes"), "degenerate triangle"));
+                }
+                (
+                    vec![shapes::get_flat_patch(triangle)],
+                    Transform::identity(),
+                    material,
+                )
+            }
+            ObjectDescription::CurveTriangle {
+                vertexes,
+                pivots,
+                koefs,
+                material,
+                ..
+            } => {
+                if koefs.iter().any(|&koef| koef.is_nan() || koef <= 0.) {
+                    return Err(invalid_field(&field("koefs"), "koefs must be positive"));
+                }
+                (
+                    vec![CurveTriangle::new(
+                        Triangle::new(vertexes.map(Vector3::from)),
+                        pivots.map(Vector3::from),
+                        *koefs,
+                    )],
+                    Transform::identity(),
+                    material,
+                )
+            }
+            ObjectDescription::CurveSphere {
+                center,
+                radius,
+                material,
+                ..
+            } => {
+                if radius.is_nan() || *radius <= 0. {
+                    return Err(invalid_field(&field("radius"), "radius must be positive"));
+                }
+                (
+                    shapes::get_curve_sphere(),
+                    Transform::new(Vector3::from(*center), Matrix3::identity(), *radius),
+                    material,
+                )
+            }
+            ObjectDescription::Mesh { path, material, .. } => {
+                let patches = shapes::load_curve_mesh(base_dir.join(path)).map_err(|error| {
+                    invalid_field(&field("path"), &format!("{}: {}", path, error))
+                })?;
+                (patches, Transform::identity(), material)
+            }
+        };
+        let transform = transform.then(&object_transform(object.transform(), &field("transform"))?);
+        let material = find_material(material_name, &field("material"))?;
+        let mut shape = Shape::new(patches, material, transform);
+        for part in shape.patches.iter_mut() {
+            part.triangulate(description.triangulation.max(1));
+        }
+        shapes.push(shape);
+    }
+
+    let mut scene = Scene::new(shapes, description.bvh);
+    scene.lights = lights;
+
+    Ok(SceneFile {
+        width: image.width,
+        height: image.height,
+        camera,
+        renderers,
+        trace_depth: description.trace_depth,
+        scene,
+    })
+}
+
+/// Read a JSON scene file, see `read_scene_file`. Errors start with the path of the file.
+pub fn load_scene_file<P: AsRef<Path>>(path: P) -> IoResult<SceneFile> {
+    let path = path.as_ref();
+    fs::read_to_string(path)
+        .and_then(|text| read_scene_file(&text, path.parent().unwrap_or_else(|| Path::new(""))))
+        .map_err(|error| IoError::new(error.kind(), format!("{}: {}", path.display(), error)))
+}
+
+fn camera(
+    description: &CameraDescription,
+    image: &ImageDescription,
+    strips: usize,
+) -> IoResult<Camera> {
+    let origin = Vector3::from(description.origin);
+    let direction = match (description.direction, description.look_at) {
+        (Some(direction), None) => unit(direction, "camera.direction")?,
+        (None, Some(look_at)) => unit((Vector3::from(look_at) - origin).into(), "camera.look_at")?,
+        _ => {
+            return Err(invalid_field(
+                "camera",
+                "expected either `direction` or `look_at`",
+            ))
+        }
+    };
+    if !(description.fov > 0. && description.fov < 180.) {
+        return Err(invalid_field(
+            "camera.fov",
+            "expected degrees between 0 and 180",
+        ));
+    }
+    Ok(Camera {
+        origin,
+        direction,
+        fov: Rad::from(Deg(description.fov)).0,
+        ratio: image.width as f32 / strips as f32 / image.height as f32,
+    })
+}
+
+fn object_transform(description: &TransformDescription, field: &str) -> IoResult<Transform> {
+    if description.scale.is_nan() || description.scale <= 0. {
+        return Err(invalid_field(field, "scale must be positive"));
+    }
+    let [x, y, z] = description.rotation;
+    let rotation =
+        Matrix3::from_angle_z(Deg(z)) * Matrix3::from_angle_y(Deg(y)) * Matrix3::from_angle_x(Deg(x));
+    Ok(Transform::new(
+        Vector3::from(description.translation),
+        rotation,
+        description.scale,
+    ))
+}
+
+fn unit(vector: [f32; 3], field: &str) -> IoResult<Vector3<f32>> {
+    let vector = Vector3::from(vector);
+    let length = vector.magnitude2();
+    if length.is_nan() || length <= 0. {
+        return Err(invalid_field(field, "expected a non zero vector"));
+    }
+    Ok(vector.normalize())
+}
+
+fn invalid_field(field: &str, message: &str) -> IoError {
+    IoError::new(ErrorKind::InvalidData, format!("{}: {}", field, message))
+}
+
@@ -1,43 +1,254 @@
-use cgmath::Vector3;
+use std::io::{Error as IoError, ErrorKind, Result as IoResult};
+use std::path::Path;
+
+use cgmath::{Deg, InnerSpace, Matrix, Matrix3, Rad, SquareMatrix, Vector3};
+use crate::materials::{Material, GREEN, RED, WHITE};
+use crate::raytracing::camera::Camera;
 use crate::raytracing::curve_triangle::CurveTriangle;
+use crate::raytracing::hit::Hit;
+use crate::raytracing::light::{AreaLight, Light};
+use crate::raytracing::mesh::Mesh;
+use crate::raytracing::ray::Ray;
 use crate::raytracing::triangle::Triangle;
+use crate::{obj, ply};
+
+
+/// Curve patches in their own coords with one material, placed into the world by the transform.
+/// Curve surfaces change with rotation and translation of their points, so the patches stay
+/// where they were built and rays are moved into their coords instead.
+pub struct Shape {
+    pub patches: Vec<CurveTriangle>,
+    pub material: Material,
+    pub transform: Transform,
+}
+
+
+/// Uniform scale, then rotation, then translation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    /// Orthonormal
+    pub rotation: Matrix3<f32>,
+    pub scale: f32,
+}
+
+
+impl Shape {
+    pub fn new(patches: Vec<CurveTriangle>, material: Material, transform: Transform) -> Shape {
+        Shape {
+            patches,
+            material,
+            transform,
+        }
+    }
+}
+
+
+impl Default for Transform {
+    fn default() -> Self {
+        Transform::identity()
+    }
+}
+
+
+impl Transform {
+    pub fn identity() -> Transform {
+        Transform {
+            translation: Vector3::new(0., 0., 0.),
+            rotation: Matrix3::identity(),
+            scale: 1.,
+        }
+    }
+
+    pub fn new(translation: Vector3<f32>, rotation: Matrix3<f32>, scale: f32) -> Transform {
+        Transform {
+            translation,
+            rotation,
+            scale,
+        }
+    }
+
+    /// Rotation around the vertical axis by degrees, then translation
+    pub fn from_rotation_y(translation: Vector3<f32>, degrees: f32) -> Transform {
+        Transform::new(translation, Matrix3::from_angle_y(Deg(degrees)), 1.)
+    }
+
+    /// This transform followed by `outer`
+    pub fn then(&self, outer: &Transform) -> Transform {
+        Transform {
+            translation: outer.point(self.translation),
+            rotation: outer.rotation * self.rotation,
+            scale: outer.scale * self.scale,
+        }
+    }
+
+    pub fn point(&self, point: Vector3<f32>) -> Vector3<f32> {
+        self.translation + self.rotation * (point * self.scale)
+    }
+
+    /// Directions and normals, unit vectors stay unit
+    pub fn direction(&self, direction: Vector3<f32>) -> Vector3<f32> {
+        self.rotation * direction
+    }
+
+    /// Ray in the coords of the shape, the direction keeps its length,
+    /// so distances along it are `1 / scale` of the world ones
+    pub fn ray_to_local(&self, ray: &Ray) -> Ray {
+        let inverse_rotation = self.rotation.transpose();
+        Ray {
+            origin: inverse_rotation * (ray.origin - self.translation) / self.scale,
+            direction: inverse_rotation * ray.direction,
+        }
+    }
+
+    /// Hit of `ray_to_local(ray)` for the world `ray`
+    pub fn hit_to_world(&self, hit: Hit, ray: &Ray) -> Hit {
+        let t = hit.t * self.scale;
+        Hit {
+            t,
+            point: ray.get_point(t),
+            normal: self.direction(hit.normal),
+            ..hit
+        }
+    }
+
+    /// Copy of the mesh in the world coords
+    pub fn mesh(&self, mesh: &Mesh) -> Mesh {
+        Mesh {
+            vertexes: mesh.vertexes.iter().map(|&vertex| self.point(vertex)).collect(),
+            normals: mesh
+                .normals
+                .as_ref()
+                .map(|normals| normals.iter().map(|&normal| self.direction(normal)).collect()),
+            ..mesh.clone()
+        }
+    }
+}
+
+
+/// Flat patch of the triangle, its normal looks where the vertexes go counter-clockwise
+pub fn get_flat_patch(triangle: Triangle) -> CurveTriangle {
+    let normal = triangle.normal();
+    CurveTriangle::from_vertex_normals(triangle, [normal; 3])
+}
 
 
-struct Shape {
-    polygons: Vec<Triangle>,
-    color:
+/// Two flat patches of the quad, the front side looks where the corners go counter-clockwise
+pub fn get_wall(corners: [Vector3<f32>; 4]) -> Vec<CurveTriangle> {
+    vec![
+        get_flat_patch(Triangle::new([corners[0], corners[1], corners[2]])),
+        get_flat_patch(Triangle::new([corners[0], corners[2], corners[3]])),
+    ]
 }
 
 
-fn get_scene() {
-    // construct walls
-    get_wall();
-    get_wall();
-    get_wall();
-    // floor
-    get_wall();
-    // ceil
-    get_wall();
-
-    // set boxes
-    get_box();
-    // set spheres
-    get_sphere();
+/// Box of the size around the origin, walls look outside
+pub fn get_box(size: Vector3<f32>) -> Vec<CurveTriangle> {
+    let half = size * 0.5;
+    let corner = |x: f32, y: f32, z: f32| Vector3::new(x * half.x, y * half.y, z * half.z);
+    let faces = [
+        // -x, +x
+        [corner(-1., -1., -1.), corner(-1., -1., 1.), corner(-1., 1., 1.), corner(-1., 1., -1.)],
+        [corner(1., -1., -1.), corner(1., 1., -1.), corner(1., 1., 1.), corner(1., -1., 1.)],
+        // -y, +y
+        [corner(-1., -1., -1.), corner(1., -1., -1.), corner(1., -1., 1.), corner(-1., -1., 1.)],
+        [corner(-1., 1., -1.), corner(-1., 1., 1.), corner(1., 1., 1.), corner(1., 1., -1.)],
+        // -z, +z
+        [corner(-1., -1., -1.), corner(-1., 1., -1.), corner(1., 1., -1.), corner(1., -1., -1.)],
+        [corner(-1., -1., 1.), corner(1., -1., 1.), corner(1., 1., 1.), corner(-1., 1., 1.)],
+    ];
+    faces.into_iter().flat_map(get_wall).collect()
 }
 
 
-fn get_wall() {
+/// Cornell box of size 2 around the origin, open to the camera at -z: red wall on the left,
+/// green on the right, a tall and a short box and a curve sphere on the short one.
+pub fn get_cornell_box() -> Vec<Shape> {
+    let diffuse = |color| Material {
+        color,
+        ..Material::default()
+    };
+    let corner = |x: f32, y: f32, z: f32| Vector3::new(x, y, z);
+    // walls look into the box
+    let walls = [
+        (
+            [corner(-1., -1., -1.), corner(-1., 1., -1.), corner(-1., 1., 1.), corner(-1., -1., 1.)],
+            RED,
+        ),
+        (
+            [corner(1., -1., -1.), corner(1., -1., 1.), corner(1., 1., 1.), corner(1., 1., -1.)],
+            GREEN,
+        ),
+        // back, floor, ceiling
+        (
+            [corner(-1., -1., 1.), corner(-1., 1., 1.), corner(1., 1., 1.), corner(1., -1., 1.)],
+            WHITE,
+        ),
+        (
+            [corner(-1., -1., -1.), corner(-1., -1., 1.), corner(1., -1., 1.), corner(1., -1., -1.)],
+            WHITE,
+        ),
+        (
+            [corner(-1., 1., -1.), corner(1., 1., -1.), corner(1., 1., 1.), corner(-1., 1., 1.)],
+            WHITE,
+        ),
+    ];
 
+    let mut shapes: Vec<Shape> = walls
+        .into_iter()
+        .map(|(corners, color)| Shape::new(get_wall(corners), diffuse(color), Transform::identity()))
+        .collect();
+    shapes.push(Shape::new(
+        get_box(Vector3::new(0.6, 1.2, 0.6)),
+        diffuse(WHITE),
+        Transform::from_rotation_y(Vector3::new(-0.35, -0.4, 0.35), 17.),
+    ));
+    shapes.push(Shape::new(
+        get_box(Vector3::new(0.6, 0.6, 0.6)),
+        diffuse(WHITE),
+        Transform::from_rotation_y(Vector3::new(0.35, -0.7, -0.3), -17.),
+    ));
+    shapes.push(Shape::new(
+        get_curve_sphere(),
+        diffuse([0.9, 0.9, 0.5]),
+        Transform::new(Vector3::new(0.35, -0.1, -0.3), Matrix3::identity(), 0.3),
+    ));
+    shapes
 }
 
 
-fn get_box() {}
+/// Square area light just under the ceiling of `get_cornell_box`, shining down
+pub fn get_cornell_box_light() -> Light {
+    let corner = |x: f32, z: f32| Vector3::new(x, 0.98, z);
+    let corners = [
+        corner(-0.25, -0.25),
+        corner(0.25, -0.25),
+        corner(0.25, 0.25),
+        corner(-0.25, 0.25),
+    ];
+    Light::Area(AreaLight::new(
+        vec![
+            Triangle::new([corners[0], corners[1], corners[2]]),
+            Triangle::new([corners[0], corners[2], corners[3]]),
+        ],
+        WHITE,
+        12.,
+    ))
+}
 
 
-fn get_sphere() {}
+/// Camera in front of the open side of `get_cornell_box`
+pub fn get_cornell_box_camera(ratio: f32) -> Camera {
+    Camera {
+        origin: Vector3::new(0., 0., -3.4),
+        direction: Vector3::new(0., 0., 1.).normalize(),
+        fov: Rad::from(Deg(90.)).0,
+        ratio,
+    }
+}
 
 
-fn get_curve_sphere() -> Vec<CurveTriangle> {
+pub fn get_curve_sphere() -> Vec<CurveTriangle> {
     let mut sphere = Vec::new();
 
     for index in (0 as i8)..(8 as i8) {
@@ -63,3 +274,24 @@ fn get_curve_sphere() -> Vec<CurveTriangle> {
     }
     sphere
 }
+
+
+/// Curve patches of an OBJ or ASCII PLY mesh, one per face, smooth by the vertex normals
+pub fn load_curve_mesh<P: AsRef<Path>>(path: P) -> IoResult<Vec<CurveTriangle>> {
+    let path = path.as_ref();
+    let extension = path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(|extension| extension.to_ascii_lowercase());
+    let mesh = match extension.as_deref() {
+        Some("obj") => obj::load_obj(path)?,
+        Some("ply") => ply::load_ply(path)?,
+        _ => {
+            return Err(IoError::new(
+                ErrorKind::InvalidInput,
+                "unknown mesh format, expected .obj or .ply",
+            ))
+        }
+    };
+    Ok(mesh.to_curve_patches())
+}
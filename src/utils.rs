@@ -4,7 +4,8 @@ use std::{
 };
 
 use cgmath::{ElementWise, Vector3};
-use glium::{self, glutin::event_loop::EventLoop};
+
+use crate::raytracing::ray::Ray;
 
 pub fn load_shaders_sources() -> Result<(String, String), IoError> {
     let vertex_shader = match std::fs::read_to_string("resources\\display.vert") {
@@ -20,24 +21,6 @@ pub fn load_shaders_sources() -> Result<(String, String), IoError> {
     return Ok((vertex_shader, fragment_shader));
 }
 
-pub fn init_window(width: u32, height: u32) -> (glium::Display, EventLoop<()>) {
-    use glium::glutin::{dpi::LogicalSize, window::WindowBuilder, Api, ContextBuilder, GlRequest};
-    let logical_dpi = LogicalSize::new(width, height);
-
-    let event_loop = EventLoop::new();
-
-    let wb = WindowBuilder::new()
-        .with_inner_size(logical_dpi)
-        .with_title("CurveRay");
-
-    let cb = ContextBuilder::new()
-        .with_vsync(true)
-        .with_gl(GlRequest::Specific(Api::OpenGl, (3, 3)));
-
-    let display = glium::Display::new(wb, cb, &event_loop).unwrap();
-    return (display, event_loop);
-}
-
 pub fn get_vectors_relation(v1: Vector3<f32>, v2: Vector3<f32>, p: Vector3<f32>) -> f32 {
     let tmp = (p - v1).div_element_wise(v2 - v1);
     if tmp.x.is_finite() {
@@ -89,6 +72,57 @@ impl<T: Iterator<Item = f32>> MinMaxIterExt for T {
     }
 }
 
+/// Small xorshift* generator for sampling, deterministic for the same seed
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        // splitmix64 spreads close seeds, xorshift needs a non zero state
+        let mut z = seed.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        Rng {
+            state: (z ^ (z >> 31)) | 1,
+        }
+    }
+
+    /// Generator seeded by the bits of the values, like the origin and direction of a ray
+    pub fn from_floats(values: &[f32]) -> Rng {
+        let seed = values.iter().fold(0u64, |seed, value| {
+            (seed.rotate_left(21) ^ value.to_bits() as u64).wrapping_mul(0x2545_f491_4f6c_dd1d)
+        });
+        Rng::new(seed)
+    }
+
+    /// Generator seeded by the origin and direction of the ray, so the same ray gets
+    /// the same samples and neighbour pixels different ones
+    pub fn from_ray(ray: &Ray) -> Rng {
+        Rng::from_floats(&[
+            ray.origin.x,
+            ray.origin.y,
+            ray.origin.z,
+            ray.direction.x,
+            ray.direction.y,
+            ray.direction.z,
+        ])
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Uniform in [0, 1)
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+}
+
 pub trait F32Ext {
     fn suppress_tail(self) -> f32;
     fn upowf(self, x: f32) -> f32;
@@ -0,0 +1,40 @@
+use glium::{self, glutin::event_loop::EventLoop, Surface};
+
+use crate::cpu_buffer::CPUBuffer;
+
+pub fn init_window(width: u32, height: u32) -> (glium::Display, EventLoop<()>) {
+    use glium::glutin::{dpi::LogicalSize, window::WindowBuilder, Api, ContextBuilder, GlRequest};
+    let logical_dpi = LogicalSize::new(width, height);
+
+    let event_loop = EventLoop::new();
+
+    let wb = WindowBuilder::new()
+        .with_inner_size(logical_dpi)
+        .with_title("CurveRay");
+
+    let cb = ContextBuilder::new()
+        .with_vsync(true)
+        .with_gl(GlRequest::Specific(Api::OpenGl, (3, 3)));
+
+    let display = glium::Display::new(wb, cb, &event_loop).unwrap();
+    return (display, event_loop);
+}
+
+/// Blit buffer to the whole window
+pub fn to_screen(cpu_buffer: &CPUBuffer, display: &glium::Display) {
+    // drawing a frame
+    let mut target = display.draw();
+    target.clear_color(0.0, 0.5, 0.3, 1.0);
+    cpu_buffer
+        .as_texture(&display)
+        .as_surface()
+        .fill(&target, glium::uniforms::MagnifySamplerFilter::Linear);
+
+    // this is for shaders
+    //
+    // target.draw(
+    //     &vertex_buffer, &index_buffer, &program,
+    //     &uniforms, &Default::default()
+    // ).unwrap();
+    target.finish().unwrap();
+}
@@ -0,0 +1,88 @@
+use cgmath::Vector3;
+use curve_ray::accuracy::{sphere_report, AccuracyOptions, AccuracyReport};
+use curve_ray::raytracing::fit::Sphere;
+use curve_ray::shapes::get_curve_sphere;
+
+fn report(
+    triangulation_accuracy: i32,
+    tessellation_tolerance: f32,
+    tessellation_max_depth: u32,
+) -> AccuracyReport {
+    let sphere = Sphere {
+        center: Vector3::new(0., 0., 0.),
+        radius: 1.,
+    };
+    let options = AccuracyOptions {
+        surface_samples: 16,
+        ray_resolution: 16,
+        triangulation_accuracy,
+        tessellation_tolerance,
+        tessellation_max_depth,
+    };
+    sphere_report(&mut get_curve_sphere(), &sphere, &options)
+}
+
+#[test]
+fn curve_sphere_is_on_the_sphere() {
+    let report = report(3, 1e-2, 4);
+    assert_eq!(report.patches, 8);
+
+    // the surface of the octants is the sphere itself
+    for surface in [&report.surface, &report.surface_sqrt] {
+        assert!(surface.radial_error.count > 0);
+        assert!(
+            surface.radial_error.max < 1e-6,
+            "{:?}",
+            surface.radial_error
+        );
+        // degrees
+        assert!(
+            surface.normal_deviation.max < 0.1,
+            "{:?}",
+            surface.normal_deviation
+        );
+    }
+
+    let intersection = &report.intersection;
+    assert!(intersection.hits > 0);
+    assert_eq!(intersection.holes, 0);
+    assert_eq!(intersection.false_hits, 0);
+    assert!(
+        intersection.radial_error.max < 1e-4,
+        "{:?}",
+        intersection.radial_error
+    );
+    assert!(
+        intersection.normal_deviation.max < 0.1,
+        "{:?}",
+        intersection.normal_deviation
+    );
+}
+
+#[test]
+fn meshes_get_closer_to_the_sphere_with_subdivision() {
+    let coarse = report(3, 1e-2, 4);
+    let fine = report(5, 1e-3, 6);
+    for triangulation in [&coarse.triangulation, &fine.triangulation] {
+        // vertexes are on the surface, centers of the flat triangles are inside
+        assert!(triangulation.vertex_radial_error.max < 1e-6);
+        assert!(triangulation.center_radial_error.max < 0.1);
+        assert!(triangulation.normal_deviation.max < 10.);
+    }
+    assert!(fine.triangulation.triangles > coarse.triangulation.triangles);
+    assert!(
+        fine.triangulation.center_radial_error.max < coarse.triangulation.center_radial_error.max
+    );
+
+    for (report, tolerance) in [(&coarse, 1e-2), (&fine, 1e-3)] {
+        let tessellation = &report.tessellation;
+        assert!(tessellation.vertex_radial_error.max < 1e-6);
+        assert!(
+            tessellation.center_radial_error.max <= tolerance,
+            "{:?} over the tolerance {}",
+            tessellation.center_radial_error,
+            tolerance
+        );
+        assert_eq!(tessellation.open_edges, 0);
+    }
+}
@@ -0,0 +1,115 @@
+use std::collections::HashMap;
+
+use cgmath::{InnerSpace, Vector3};
+use curve_ray::raytracing::{curve_triangle::CurveTriangle, triangle::Triangle};
+use curve_ray::shapes::get_curve_sphere;
+
+const MAX_DEPTH: u32 = 8;
+
+/// Subdivisions of the base edges of the grid the adaptive vertexes lie on
+const GRID: usize = 1 << (MAX_DEPTH + 1);
+
+/// Points inside every triangle per side where the deviation is measured
+const SAMPLES: usize = 6;
+
+/// Smooth patches: an octant of the sphere and a patch of vertex normals like in meshes
+fn patches() -> Vec<CurveTriangle> {
+    let vertexes = [
+        Vector3::new(1., 0.2, 0.1),
+        Vector3::new(0.3, 1., 0.2),
+        Vector3::new(0.1, 0.3, 1.),
+    ];
+    let center = Vector3::new(0.1, 0.05, 0.15);
+    vec![
+        get_curve_sphere().swap_remove(0),
+        CurveTriangle::from_vertex_normals(
+            Triangle::new(vertexes),
+            vertexes.map(|vertex| (vertex + center).normalize()),
+        ),
+    ]
+}
+
+fn key(point: Vector3<f32>) -> [u32; 3] {
+    point.map(f32::to_bits).into()
+}
+
+/// Barycentric coords of the surface points of the grid, vertexes of the adaptive
+/// triangulation are exactly some of them
+fn grid_barys(patch: &CurveTriangle) -> HashMap<[u32; 3], Vector3<f32>> {
+    let mut barys = HashMap::new();
+    for i in 0..=GRID {
+        for j in 0..=GRID - i {
+            let (w1, w2) = (i as f32 / GRID as f32, j as f32 / GRID as f32);
+            let bary = Vector3::new(1. - w1 - w2, w1, w2);
+            let point = patch.get_surface_point_by_bary(bary);
+            barys.insert(key(point), bary);
+        }
+    }
+    barys
+}
+
+#[test]
+fn adaptive_triangles_stay_within_tolerance() {
+    for patch in patches().iter_mut() {
+        let barys = grid_barys(patch);
+        for tolerance in [1e-2, 3e-3, 1e-3] {
+            let report = patch.triangulate_adaptive(tolerance, MAX_DEPTH);
+            assert!(report.max_error <= tolerance);
+
+            let mut max_deviation: f32 = 0.;
+            for triangle in patch.triangulation.iter() {
+                let corners = triangle.vertexes.map(|vertex| barys[&key(vertex)]);
+                for i in 0..=SAMPLES {
+                    for j in 0..=SAMPLES - i {
+                        let (u, v) = (i as f32 / SAMPLES as f32, j as f32 / SAMPLES as f32);
+                        let w = 1. - u - v;
+                        let flat = triangle.vertexes[0] * w
+                            + triangle.vertexes[1] * u
+                            + triangle.vertexes[2] * v;
+                        let surface = patch.get_surface_point_by_bary(
+                            corners[0] * w + corners[1] * u + corners[2] * v,
+                        );
+                        max_deviation = max_deviation.max((surface - flat).magnitude());
+                    }
+                }
+            }
+            assert!(
+                max_deviation <= tolerance,
+                "deviation {} above the tolerance {}",
+                max_deviation,
+                tolerance
+            );
+        }
+    }
+}
+
+#[test]
+fn tighter_tolerance_gives_more_triangles() {
+    for patch in patches().iter_mut() {
+        let counts: Vec<usize> = [1e-1, 1e-2, 1e-3, 1e-4]
+            .iter()
+            .map(|&tolerance| patch.triangulate_adaptive(tolerance, MAX_DEPTH).triangles)
+            .collect();
+        assert!(
+            counts.windows(2).all(|pair| pair[0] < pair[1]),
+            "triangles {:?}",
+            counts
+        );
+    }
+}
+
+#[test]
+fn triangulate_replaces_previous_triangles() {
+    let mut patch = get_curve_sphere().swap_remove(0);
+    patch.triangulate(3);
+    patch.triangulate(3);
+    assert_eq!(patch.triangulation.len(), 9);
+
+    let report = patch.triangulate_adaptive(1e-3, 6);
+    assert_eq!(patch.triangulation.len(), report.triangles);
+    let report = patch.triangulate_adaptive(1e-2, 6);
+    assert_eq!(patch.triangulation.len(), report.triangles);
+
+    patch.triangulate(4);
+    assert_eq!(patch.triangulation.len(), 16);
+}
@@ -0,0 +1,123 @@
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn run(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_curve_ray"))
+        .args(args)
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .output()
+        .expect("binary runs")
+}
+
+fn temp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("curve_ray_cli_{}_{}", std::process::id(), name))
+}
+
+/// Exit code and the stderr containing `message`
+fn assert_fails(args: &[&str], code: i32, message: &str) {
+    let output = run(args);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_eq!(output.status.code(), Some(code), "{:?}: {}", args, stderr);
+    assert!(
+        stderr.contains(message),
+        "{:?}: `{}` not in {}",
+        args,
+        message,
+        stderr
+    );
+}
+
+#[test]
+fn usage_errors_exit_with_2() {
+    assert_fails(
+        &["render", "-o", "x.ppm", "--bogus"],
+        2,
+        "unknown option `--bogus`",
+    );
+    assert_fails(
+        &["accuracy", "--width", "4"],
+        2,
+        "`--width` is not an option of `accuracy`",
+    );
+    assert_fails(&["render", "-o"], 2, "-o requires a value");
+    assert_fails(
+        &["render", "-o", "x.ppm", "--spp"],
+        2,
+        "--spp requires a value",
+    );
+    assert_fails(
+        &["render", "--width", "8"],
+        2,
+        "`render` requires -o <path>",
+    );
+    assert_fails(
+        &["render", "a.json", "b.json", "-o", "x.ppm"],
+        2,
+        "unexpected argument `b.json`",
+    );
+    assert_fails(
+        &["render", "-o", "x.ppm", "--png16"],
+        2,
+        "--png16 requires a .png output, got x.ppm",
+    );
+    // the usage follows the error
+    assert_fails(&["render", "-o"], 2, "usage: curve_ray");
+}
+
+#[test]
+fn non_positive_numbers_are_usage_errors() {
+    for option in ["--width", "--height", "--spp"] {
+        for value in ["0", "-3", "abc", "1.5"] {
+            assert_fails(
+                &["render", "-o", "x.ppm", option, value],
+                2,
+                &format!("{} requires a positive number, got `{}`", option, value),
+            );
+        }
+    }
+}
+
+#[test]
+fn first_argument_other_than_command_is_scene() {
+    // not loaded as scenes, but not mistaken for unknown commands either
+    for scene in ["scenes/missing_scene", "scenes/MISSING.JSON", "rendr"] {
+        let output = temp_path("missing.ppm");
+        assert_fails(
+            &[scene, "-o", output.to_str().unwrap()],
+            1,
+            &format!("can't load {}", scene),
+        );
+        assert!(!output.exists());
+    }
+}
+
+#[test]
+fn render_saves_image() {
+    let output = temp_path("spheres.ppm");
+    let result = run(&[
+        "scenes/spheres.json",
+        "--width",
+        "8",
+        "--height",
+        "6",
+        "--renderer",
+        "curve",
+        "-o",
+        output.to_str().unwrap(),
+    ]);
+    assert!(
+        result.status.success(),
+        "{}",
+        String::from_utf8_lossy(&result.stderr)
+    );
+    let image = std::fs::read(&output).unwrap();
+    std::fs::remove_file(&output).unwrap();
+    assert!(image.starts_with(b"P6\n8 6\n"));
+}
+
+#[test]
+fn help_exits_with_0() {
+    let output = run(&["--help"]);
+    assert!(output.status.success());
+    assert!(String::from_utf8_lossy(&output.stdout).contains("usage: curve_ray"));
+}
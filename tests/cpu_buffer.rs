@@ -0,0 +1,82 @@
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use curve_ray::cpu_buffer::{CPUBuffer, ImageFormat};
+
+/// Red, green, blue and gray pixels, the first row is the top of the image
+fn buffer() -> CPUBuffer {
+    let mut buffer = CPUBuffer::new(2, 2);
+    buffer[(0, 0)] = [1., 0., 0.];
+    buffer[(1, 0)] = [0., 1., 0.];
+    buffer[(0, 1)] = [0., 0., 1.];
+    buffer[(1, 1)] = [0.5, 0.25, 0.];
+    buffer
+}
+
+/// Bytes of the buffer saved in the format
+fn saved(format: ImageFormat) -> Vec<u8> {
+    let path: PathBuf = std::env::temp_dir().join(format!(
+        "curve_ray_buffer_{}_{:?}",
+        std::process::id(),
+        format
+    ));
+    buffer().save(&path, format).unwrap();
+    let bytes = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    bytes
+}
+
+fn decode_png(bytes: Vec<u8>) -> (png::OutputInfo, Vec<u8>) {
+    let mut reader = png::Decoder::new(Cursor::new(bytes)).read_info().unwrap();
+    let mut pixels = vec![0; reader.output_buffer_size().expect("buffer size")];
+    let info = reader.next_frame(&mut pixels).unwrap();
+    pixels.truncate(info.buffer_size());
+    (info, pixels)
+}
+
+#[test]
+fn saves_ppm() {
+    let bytes = saved(ImageFormat::Ppm);
+    let header = b"P6\n2 2\n255\n";
+    assert_eq!(&bytes[..header.len()], header);
+    assert_eq!(
+        &bytes[header.len()..],
+        &[255, 0, 0, 0, 255, 0, 0, 0, 255, 127, 63, 0]
+    );
+}
+
+#[test]
+fn saves_png() {
+    let (info, pixels) = decode_png(saved(ImageFormat::Png));
+    assert_eq!((info.width, info.height), (2, 2));
+    assert_eq!(info.color_type, png::ColorType::Rgb);
+    assert_eq!(info.bit_depth, png::BitDepth::Eight);
+    assert_eq!(pixels, [255, 0, 0, 0, 255, 0, 0, 0, 255, 127, 63, 0]);
+}
+
+#[test]
+fn saves_16_bit_png() {
+    let (info, pixels) = decode_png(saved(ImageFormat::Png16));
+    assert_eq!((info.width, info.height), (2, 2));
+    assert_eq!(info.color_type, png::ColorType::Rgb);
+    assert_eq!(info.bit_depth, png::BitDepth::Sixteen);
+    let channels: Vec<u16> = pixels
+        .chunks(2)
+        .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+        .collect();
+    assert_eq!(
+        channels,
+        [65535, 0, 0, 0, 65535, 0, 0, 0, 65535, 32767, 16383, 0]
+    );
+}
+
+#[test]
+fn format_by_extension() {
+    assert_eq!(
+        ImageFormat::from_path("a/image.PPM"),
+        Some(ImageFormat::Ppm)
+    );
+    assert_eq!(ImageFormat::from_path("image.png"), Some(ImageFormat::Png));
+    assert_eq!(ImageFormat::from_path("image.jpg"), None);
+    assert_eq!(ImageFormat::from_path("image"), None);
+}
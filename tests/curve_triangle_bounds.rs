@@ -0,0 +1,72 @@
+use bvh::aabb::Bounded;
+use cgmath::Vector3;
+use curve_ray::raytracing::{curve_triangle::CurveTriangle, triangle::Triangle};
+use curve_ray::shapes::get_curve_sphere;
+
+/// Subdivisions of the base edges, much denser than the grid used for the bounds
+const SAMPLES: usize = 300;
+
+fn assert_surface_inside_aabb(curve_triangle: &CurveTriangle) {
+    let aabb = curve_triangle.aabb();
+    for i in 0..=SAMPLES {
+        for j in 0..=SAMPLES - i {
+            let (w1, w2) = (i as f32 / SAMPLES as f32, j as f32 / SAMPLES as f32);
+            let bary = Vector3::new((1. - w1 - w2).max(0.), w1, w2);
+            let point = curve_triangle.get_surface_point_by_bary(bary);
+            for axis in 0..3 {
+                assert!(
+                    aabb.min[axis] <= point[axis] && point[axis] <= aabb.max[axis],
+                    "surface point {:?} at {:?} is outside of {:?}",
+                    point,
+                    bary,
+                    aabb,
+                );
+            }
+        }
+    }
+}
+
+fn patch(vertexes: [[f32; 3]; 3], pivots: [[f32; 3]; 3], curve_koefs: [f32; 3]) -> CurveTriangle {
+    CurveTriangle::new(
+        Triangle::new(vertexes.map(Vector3::from)),
+        pivots.map(Vector3::from),
+        curve_koefs,
+    )
+}
+
+#[test]
+fn sphere_octants_inside_aabb() {
+    for part in get_curve_sphere().iter() {
+        assert_surface_inside_aabb(part);
+    }
+}
+
+#[test]
+fn asymmetric_pivots_inside_aabb() {
+    assert_surface_inside_aabb(&patch(
+        [[0., 0., 0.], [1., 1., 0.3], [-0.2, 0.9, 1.1]],
+        [[0.3, 0.2, -0.4], [0.1, 0.8, 0.2], [-0.3, 0.3, 0.2]],
+        [2., 2., 2.],
+    ));
+    assert_surface_inside_aabb(&patch(
+        [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+        [[0.2, 0.1, -0.3], [-0.1, 0., 0.], [0.4, -0.2, 0.1]],
+        [2., 2., 2.],
+    ));
+}
+
+#[test]
+fn curve_koefs_inside_aabb() {
+    for curve_koefs in [[1., 1., 1.], [1.5, 3., 2.5], [4., 1.2, 2.], [6., 6., 6.]] {
+        assert_surface_inside_aabb(&patch(
+            [[1., 0., 0.], [0., 1., 0.], [0., 0., 1.]],
+            [[0., 0., 0.], [0., 0., 0.], [0., 0., 0.]],
+            curve_koefs,
+        ));
+        assert_surface_inside_aabb(&patch(
+            [[0., 0., 0.], [1., 1., 0.3], [-0.2, 0.9, 1.1]],
+            [[0.3, 0.2, -0.4], [0.1, 0.8, 0.2], [-0.3, 0.3, 0.2]],
+            curve_koefs,
+        ));
+    }
+}
@@ -0,0 +1,68 @@
+use cgmath::{InnerSpace, Vector3};
+use curve_ray::raytracing::{
+    fit::{fit_curve_triangle, surface_error, Cylinder, FitOptions, Sphere, TargetSurface, Torus},
+    triangle::Triangle,
+};
+use curve_ray::shapes::get_curve_sphere;
+
+#[test]
+fn sphere_octants_are_on_sphere() {
+    let sphere = Sphere {
+        center: Vector3::new(0., 0., 0.),
+        radius: 1.,
+    };
+    for part in get_curve_sphere().iter() {
+        let error = surface_error(part, &sphere, 64);
+        assert!(error.max < 1e-5, "octant is off the sphere: {:?}", error);
+    }
+}
+
+#[test]
+fn sphere_fit_keeps_octant() {
+    let sphere = Sphere {
+        center: Vector3::new(0., 0., 0.),
+        radius: 1.,
+    };
+    let base = Triangle::new([
+        Vector3::new(1., 0., 0.),
+        Vector3::new(0., 1., 0.),
+        Vector3::new(0., 0., 1.),
+    ]);
+    let (patch, report) = fit_curve_triangle(base, &sphere, &FitOptions::default());
+    assert!(report.error.max < 1e-5, "{:?}", report);
+    for pivot in patch.pivots.iter() {
+        assert!(pivot.magnitude() < 1e-4, "pivot {:?} is not in the center", pivot);
+    }
+}
+
+#[test]
+fn fit_improves_start_patch() {
+    let cylinder = Cylinder {
+        origin: Vector3::new(0., 0., 0.),
+        axis: Vector3::unit_z(),
+        radius: 1.,
+    };
+    let base = Triangle::new([
+        Vector3::new(1., 0., 0.),
+        Vector3::new(0., 1., 0.),
+        Vector3::new(1., 0., 1.),
+    ]);
+    let (_, report) = fit_curve_triangle(base, &cylinder, &FitOptions::default());
+    assert!(report.error.rms < report.initial.rms, "{:?}", report);
+
+    let torus = Torus {
+        center: Vector3::new(0., 0., 0.),
+        axis: Vector3::unit_z(),
+        major_radius: 2.,
+        minor_radius: 0.5,
+    };
+    let base = Triangle::new([
+        Vector3::new(2.5, 0., 0.),
+        Vector3::new(0., 2.5, 0.),
+        Vector3::new(2., 0., 0.5),
+    ]);
+    // same torus given as a closure
+    let sdf = |point: Vector3<f32>| torus.distance(point);
+    let (_, report) = fit_curve_triangle(base, &sdf, &FitOptions::default());
+    assert!(report.error.rms < report.initial.rms * 0.5, "{:?}", report);
+}
@@ -0,0 +1,208 @@
+use cgmath::{InnerSpace, Vector3};
+use curve_ray::raytracing::{
+    aabb::AABBox,
+    hit::{Hit, Intersect},
+    ray::Ray,
+    triangle::Triangle,
+};
+use curve_ray::shapes::{get_curve_sphere, get_flat_patch, get_wall};
+
+const EPSILON: f32 = 1e-5;
+
+fn ray(origin: [f32; 3], direction: [f32; 3]) -> Ray {
+    Ray {
+        origin: Vector3::from(origin),
+        direction: Vector3::from(direction),
+    }
+}
+
+fn assert_close(actual: Vector3<f32>, expected: Vector3<f32>, tolerance: f32) {
+    assert!(
+        (actual - expected).magnitude() < tolerance,
+        "{:?} instead of {:?}",
+        actual,
+        expected
+    );
+}
+
+/// Point along the ray, unit normal against the ray, `front_face` by the outward normal
+fn assert_consistent(hit: &Hit, ray: &Ray) {
+    assert!(hit.t > 0.);
+    assert_close(hit.point, ray.origin + ray.direction * hit.t, EPSILON);
+    assert!((hit.normal.magnitude() - 1.).abs() < EPSILON);
+    assert!(
+        hit.normal.dot(ray.direction) < 0.,
+        "{:?} faces away",
+        hit.normal
+    );
+    assert_eq!(hit.front_face, hit.outward_normal().dot(ray.direction) < 0.);
+}
+
+#[test]
+fn triangle_hits() {
+    // outward normal is +z by the winding
+    let triangle = Triangle::new([
+        Vector3::new(0., 0., 1.),
+        Vector3::new(1., 0., 1.),
+        Vector3::new(0., 1., 1.),
+    ]);
+
+    let from_below = ray([0.25, 0.25, -1.], [0., 0., 2.]);
+    let hit = triangle.intersect(&from_below).unwrap();
+    assert_consistent(&hit, &from_below);
+    assert!((hit.t - 1.).abs() < EPSILON);
+    assert_close(hit.point, Vector3::new(0.25, 0.25, 1.), EPSILON);
+    assert_close(hit.bary, Vector3::new(0.5, 0.25, 0.25), EPSILON);
+    assert_close(hit.normal, Vector3::new(0., 0., -1.), EPSILON);
+    assert!(!hit.front_face);
+
+    let from_above = ray([0.5, 0.25, 3.], [0., 0., -1.]);
+    let hit = triangle.intersect(&from_above).unwrap();
+    assert_consistent(&hit, &from_above);
+    assert!((hit.t - 2.).abs() < EPSILON);
+    assert_close(hit.bary, Vector3::new(0.25, 0.5, 0.25), EPSILON);
+    assert_close(hit.normal, Vector3::new(0., 0., 1.), EPSILON);
+    assert!(hit.front_face);
+
+    // behind the origin, beside the triangle, parallel to it
+    assert!(triangle
+        .intersect(&ray([0.25, 0.25, 2.], [0., 0., 1.]))
+        .is_none());
+    assert!(triangle
+        .intersect(&ray([0.8, 0.8, -1.], [0., 0., 1.]))
+        .is_none());
+    assert!(triangle
+        .intersect(&ray([0.25, 0.25, 0.], [1., 0., 0.]))
+        .is_none());
+}
+
+#[test]
+fn aabbox_hits() {
+    let unit_box = AABBox {
+        min_x: 0.,
+        max_x: 1.,
+        min_y: 0.,
+        max_y: 1.,
+        min_z: 0.,
+        max_z: 1.,
+    };
+
+    let from_front = ray([0.5, 0.5, -1.], [0., 0., 1.]);
+    let hit = unit_box.intersect(&from_front).unwrap();
+    assert_consistent(&hit, &from_front);
+    assert!((hit.t - 1.).abs() < EPSILON);
+    assert_close(hit.point, Vector3::new(0.5, 0.5, 0.), EPSILON);
+    assert_close(hit.normal, Vector3::new(0., 0., -1.), EPSILON);
+    assert!(hit.front_face);
+
+    let oblique = ray([-1., 0.5, 0.2], [1., 0.1, 0.2]);
+    let hit = unit_box.intersect(&oblique).unwrap();
+    assert_consistent(&hit, &oblique);
+    assert!((hit.t - 1.).abs() < EPSILON);
+    assert_close(hit.point, Vector3::new(0., 0.6, 0.4), EPSILON);
+    assert_close(hit.normal, Vector3::new(-1., 0., 0.), EPSILON);
+
+    // exit point from inside
+    let from_inside = ray([0.5, 0.5, 0.5], [2., 0., 0.]);
+    let hit = unit_box.intersect(&from_inside).unwrap();
+    assert_consistent(&hit, &from_inside);
+    assert!((hit.t - 0.25).abs() < EPSILON);
+    assert_close(hit.point, Vector3::new(1., 0.5, 0.5), EPSILON);
+    assert_close(hit.outward_normal(), Vector3::new(1., 0., 0.), EPSILON);
+    assert!(!hit.front_face);
+
+    assert!(unit_box
+        .intersect(&ray([0.5, 0.5, 2.], [0., 0., 1.]))
+        .is_none());
+    assert!(unit_box
+        .intersect(&ray([1.5, 0.5, -1.], [0., 0., 1.]))
+        .is_none());
+}
+
+#[test]
+fn curve_triangle_hits() {
+    let octant = get_curve_sphere().swap_remove(0);
+    for direction in [[1., 1., 1.], [0.2, 0.5, 1.], [1., 0.3, 0.1], [0.4, 1., 0.6]] {
+        let direction = Vector3::from(direction).normalize();
+
+        // towards the center of the unit sphere from outside
+        let from_outside = Ray {
+            origin: direction * 3.,
+            direction: -direction * 0.5,
+        };
+        let hit = octant.intersect(&from_outside).unwrap();
+        assert_consistent(&hit, &from_outside);
+        assert!((hit.t - 4.).abs() < 1e-2, "t {}", hit.t);
+        assert!(hit.front_face);
+        // the hit is on the line from the root point through the base point of its bary,
+        // at the distance of the surface point of the same bary
+        assert_close(octant.project_to_base(hit.point).unwrap(), hit.bary, 1e-4);
+        let surface_distance =
+            (octant.get_surface_point_by_bary(hit.bary) - octant.root_point).magnitude();
+        assert!(((hit.point - octant.root_point).magnitude() - surface_distance).abs() < 1e-4);
+        assert_close(
+            octant.hit_normal_at_bary(hit.bary),
+            hit.outward_normal(),
+            EPSILON,
+        );
+        // and approximates the sphere
+        assert!((hit.point.magnitude() - 1.).abs() < 1e-3);
+        assert!(hit.normal.dot(direction) > 0.999);
+
+        let from_inside = Ray {
+            origin: direction * 0.5,
+            direction,
+        };
+        let hit = octant.intersect(&from_inside).unwrap();
+        assert_consistent(&hit, &from_inside);
+        assert!((hit.t - 0.5).abs() < 1e-2, "t {}", hit.t);
+        assert!(!hit.front_face);
+        assert!(hit.outward_normal().dot(direction) > 0.999);
+
+        // pointing away from the patch
+        let away = Ray {
+            origin: direction * 3.,
+            direction,
+        };
+        assert!(octant.intersect(&away).is_none());
+    }
+}
+
+#[test]
+fn flat_patch_hits_have_plane_normal() {
+    let triangle = Triangle::new([
+        Vector3::new(-1., -1., -1.),
+        Vector3::new(1., -0.5, 0.5),
+        Vector3::new(-0.5, 1., 0.),
+    ]);
+    // a wall like the left one of the Cornell box, looking along +x
+    let mut patches = get_wall([
+        Vector3::new(-1., -1., 1.),
+        Vector3::new(-1., -1., -1.),
+        Vector3::new(-1., 1., -1.),
+        Vector3::new(-1., 1., 1.),
+    ]);
+    patches.push(get_flat_patch(triangle));
+    for patch in patches.iter() {
+        let normal = patch.base.normal();
+        let center = patch.get_surface_point_by_bary(Vector3::new(1., 1., 1.) / 3.);
+        for bary in [[0.6, 0.2, 0.2], [0.1, 0.1, 0.8], [0.3, 0.65, 0.05]] {
+            let target = patch.base.vertexes[0] * bary[0]
+                + patch.base.vertexes[1] * bary[1]
+                + patch.base.vertexes[2] * bary[2];
+            for side in [1., -1.] {
+                let origin = center + (normal * 2. + Vector3::new(0.3, -0.2, 0.1)) * side;
+                let ray = Ray {
+                    origin,
+                    direction: target - origin,
+                };
+                let hit = patch.intersect(&ray).unwrap();
+                assert_consistent(&hit, &ray);
+                assert!((hit.t - 1.).abs() < EPSILON);
+                assert_close(hit.point, target, EPSILON);
+                assert_close(hit.outward_normal(), normal, 1e-6);
+                assert_eq!(hit.front_face, side > 0.);
+            }
+        }
+    }
+}
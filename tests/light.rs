@@ -0,0 +1,121 @@
+use cgmath::{InnerSpace, Vector3};
+use curve_ray::materials::WHITE;
+use curve_ray::raytracing::{
+    light::{AreaLight, Light},
+    triangle::Triangle,
+};
+use curve_ray::shapes::{get_cornell_box_light, get_wall};
+use curve_ray::utils::Rng;
+
+const SAMPLES: usize = 20000;
+
+/// Square of the side 0.5 at y = 1 looking down, made of triangulated patches
+fn square_light() -> AreaLight {
+    let corner = |x: f32, z: f32| Vector3::new(x, 1., z);
+    let mut patches = get_wall([
+        corner(-0.25, -0.25),
+        corner(0.25, -0.25),
+        corner(0.25, 0.25),
+        corner(-0.25, 0.25),
+    ]);
+    for patch in patches.iter_mut() {
+        patch.triangulate(3);
+    }
+    AreaLight::from_patches(&patches, WHITE, 3.)
+}
+
+/// Solid angle of the square of the side seen from the distance on its axis
+fn square_solid_angle(side: f32, distance: f32) -> f32 {
+    let side2 = side * side;
+    4. * (side2 / (side2 + 4. * distance * distance)).asin()
+}
+
+#[test]
+fn patch_lights_keep_the_area_and_shine_from_the_front() {
+    let light = square_light();
+    assert!((light.area() - 0.25).abs() < 1e-5, "area {}", light.area());
+    for triangle in light.triangles.iter() {
+        assert!(
+            (triangle.normal() - Vector3::new(0., -1., 0.)).magnitude() < 1e-5,
+            "{:?} looks away from the front",
+            triangle.normal()
+        );
+    }
+
+    let light = Light::Area(light);
+    let mut rng = Rng::new(7);
+    for _ in 0..100 {
+        assert!(light.sample(Vector3::new(0.1, 0., 0.2), &mut rng).is_some());
+        assert!(light.sample(Vector3::new(0.1, 2., 0.2), &mut rng).is_none());
+    }
+}
+
+#[test]
+fn samples_lie_on_the_light() {
+    let point = Vector3::new(0.3, -0.5, -0.2);
+    for (light, height) in [
+        (Light::Area(square_light()), 1.),
+        (get_cornell_box_light(), 0.98),
+    ] {
+        let mut rng = Rng::new(3);
+        for _ in 0..1000 {
+            let sample = light.sample(point, &mut rng).expect("sample");
+            assert!((sample.direction.magnitude() - 1.).abs() < 1e-5);
+            let on_light = point + sample.direction * sample.distance;
+            assert!((on_light.y - height).abs() < 1e-5, "{:?}", on_light);
+            assert!(
+                on_light.x.abs() <= 0.25 + 1e-5 && on_light.z.abs() <= 0.25 + 1e-5,
+                "{:?} is off the light",
+                on_light
+            );
+        }
+    }
+}
+
+#[test]
+fn sample_weights_integrate_over_the_area() {
+    // radiance over the intensity averages to the solid angle of the light
+    let light = Light::Area(square_light());
+    let mut rng = Rng::new(11);
+    for distance in [0.3, 1., 3.] {
+        let point = Vector3::new(0., 1. - distance, 0.);
+        let mean = (0..SAMPLES)
+            .map(|_| light.sample(point, &mut rng).expect("sample").radiance[0] / 3.)
+            .sum::<f32>()
+            / SAMPLES as f32;
+        let expected = square_solid_angle(0.5, distance);
+        assert!(
+            (mean - expected).abs() < 0.02 * expected,
+            "{} instead of {} at the distance {}",
+            mean,
+            expected,
+            distance
+        );
+    }
+}
+
+#[test]
+fn triangles_are_picked_by_area() {
+    // both look down, the second triangle is three times larger
+    let corner = |x: f32, z: f32| Vector3::new(x, 0., z);
+    let light = Light::Area(AreaLight::new(
+        vec![
+            Triangle::new([corner(0., 0.), corner(0.5, 0.), corner(0., 1.)]),
+            Triangle::new([corner(0.5, 0.), corner(1.5, 1.), corner(0., 1.)]),
+        ],
+        WHITE,
+        1.,
+    ));
+    let point = Vector3::new(0.5, -1., 0.5);
+    let mut rng = Rng::new(5);
+    let in_first = (0..SAMPLES)
+        .filter(|_| {
+            let sample = light.sample(point, &mut rng).expect("sample");
+            let on_light = point + sample.direction * sample.distance;
+            // the edge between the triangles goes from (0.5, 0) to (0, 1)
+            2. * on_light.x + on_light.z < 1.
+        })
+        .count();
+    let part = in_first as f32 / SAMPLES as f32;
+    assert!((part - 0.25).abs() < 0.01, "{} of the samples", part);
+}
@@ -0,0 +1,90 @@
+use bvh::aabb::Bounded;
+use cgmath::{InnerSpace, Vector3};
+use curve_ray::raytracing::{hit::Intersect, mesh::Mesh, ray::Ray, triangle::Triangle};
+use curve_ray::shapes::{get_curve_sphere, get_flat_patch};
+use curve_ray::utils::Rng;
+
+fn sphere_mesh() -> Mesh {
+    let mut patches = get_curve_sphere();
+    for patch in patches.iter_mut() {
+        patch.triangulate(4);
+    }
+    Mesh::from_triangulations(&patches)
+}
+
+#[test]
+fn intersect_face_agrees_with_triangle() {
+    let mesh = sphere_mesh();
+    let mut rng = Rng::new(5);
+    let mut hits = 0;
+    for _ in 0..500 {
+        let origin = Vector3::new(rng.next_f32() - 0.5, rng.next_f32() - 0.5, -3.) * 2.;
+        let target = Vector3::new(
+            rng.next_f32() - 0.5,
+            rng.next_f32() - 0.5,
+            rng.next_f32() - 0.5,
+        );
+        let ray = Ray {
+            origin,
+            direction: target - origin,
+        };
+        for face in 0..mesh.indices.len() {
+            let expected = mesh.face(face).intersect(&ray);
+            let actual = mesh.intersect_face(face, &ray);
+            let (expected, actual) = match (expected, actual) {
+                (Some(expected), Some(actual)) => (expected, actual),
+                (None, None) => continue,
+                (expected, actual) => {
+                    // only rays through an edge may differ
+                    let hit = expected.or(actual).unwrap();
+                    assert!(
+                        hit.bary.x.min(hit.bary.y).min(hit.bary.z) < 1e-5,
+                        "face {} at {:?}",
+                        face,
+                        hit.bary
+                    );
+                    continue;
+                }
+            };
+            hits += 1;
+            assert!((expected.t - actual.t).abs() < 1e-5);
+            assert!((expected.bary - actual.bary).magnitude() < 1e-5);
+            assert!((expected.point - actual.point).magnitude() < 1e-5);
+            assert!(expected.normal.dot(actual.normal) > 1. - 1e-5);
+            assert_eq!(expected.front_face, actual.front_face);
+            assert_eq!(actual.primitive_id, face);
+        }
+    }
+    assert!(hits > 500, "only {} hits", hits);
+}
+
+#[test]
+fn faces_have_boxes_of_their_triangles() {
+    let mesh = sphere_mesh();
+    for (face, mesh_face) in mesh.faces().iter().enumerate() {
+        assert_eq!(mesh_face.face, face);
+        let (actual, expected) = (mesh_face.aabb(), mesh.face(face).aabb());
+        assert_eq!((actual.min, actual.max), (expected.min, expected.max));
+    }
+}
+
+#[test]
+fn tessellation_welds_close_corners() {
+    let corner = |x: f32, y: f32| Vector3::new(x, y, 0.);
+    // the shared corners are a bit apart and on different sides of zero
+    let patches = [
+        get_flat_patch(Triangle::new([
+            corner(0., 0.),
+            corner(1., 0.),
+            corner(1., 1.),
+        ])),
+        get_flat_patch(Triangle::new([
+            corner(-4e-6, 3e-6),
+            corner(1. + 2e-6, 1.),
+            corner(0., 1.),
+        ])),
+    ];
+    let mesh = Mesh::tessellate(&patches, 1e-3, 4);
+    assert_eq!(mesh.indices.len(), 2);
+    assert_eq!(mesh.vertexes.len(), 4);
+}
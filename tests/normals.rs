@@ -0,0 +1,90 @@
+use cgmath::{InnerSpace, Vector3};
+use curve_ray::shapes::get_curve_sphere;
+
+/// Points along every shared edge
+const EDGE_SAMPLES: usize = 64;
+
+/// Largest angle between the normals of two patches at the same point of a shared edge, radians
+const MAX_EDGE_ANGLE: f32 = 1e-3;
+
+fn bary_on_edge(first: usize, second: usize, s: f32) -> Vector3<f32> {
+    let mut bary = Vector3::new(0., 0., 0.);
+    bary[first] = 1. - s;
+    bary[second] = s;
+    bary
+}
+
+#[test]
+fn sphere_octant_normals_agree_on_shared_edges() {
+    let sphere = get_curve_sphere();
+    let mut shared_edges = 0;
+    for (a_index, a) in sphere.iter().enumerate() {
+        for b in sphere[a_index + 1..].iter() {
+            // vertexes of `a` and the matching vertexes of `b`
+            let shared: Vec<(usize, usize)> = (0..3)
+                .filter_map(|i| {
+                    (0..3)
+                        .find(|&j| a.base.vertexes[i] == b.base.vertexes[j])
+                        .map(|j| (i, j))
+                })
+                .collect();
+            if shared.len() != 2 {
+                continue;
+            }
+            shared_edges += 1;
+            let [(a_first, b_first), (a_second, b_second)] = [shared[0], shared[1]];
+
+            for sample in 0..=EDGE_SAMPLES {
+                let s = sample as f32 / EDGE_SAMPLES as f32;
+                let a_bary = bary_on_edge(a_first, a_second, s);
+                let b_bary = bary_on_edge(b_first, b_second, s);
+                let point = a.get_surface_point_by_bary(a_bary);
+                assert!(
+                    (point - b.get_surface_point_by_bary(b_bary)).magnitude() < 1e-5,
+                    "patches part on the edge at {:?}",
+                    point
+                );
+
+                let (a_normal, b_normal) = (a.normal_at_bary(a_bary), b.normal_at_bary(b_bary));
+                let angle = a_normal.dot(b_normal).min(1.).acos();
+                assert!(
+                    angle < MAX_EDGE_ANGLE,
+                    "normals {:?} and {:?} at {:?} differ by {} rad",
+                    a_normal,
+                    b_normal,
+                    point,
+                    angle
+                );
+            }
+        }
+    }
+    // every octant touches three others
+    assert_eq!(shared_edges, 12);
+}
+
+#[test]
+fn sphere_octant_normals_are_unit_and_outward() {
+    const SAMPLES: usize = 16;
+    for part in get_curve_sphere().iter() {
+        for i in 0..=SAMPLES {
+            for j in 0..=SAMPLES - i {
+                let (w1, w2) = (i as f32 / SAMPLES as f32, j as f32 / SAMPLES as f32);
+                let bary = Vector3::new((1. - w1 - w2).max(0.), w1, w2);
+                let point = part.get_surface_point_by_bary(bary);
+                let normal = part.normal_at_bary(bary);
+                assert!(
+                    (normal.magnitude() - 1.).abs() < 1e-5,
+                    "normal {:?} is not unit",
+                    normal
+                );
+                // the unit sphere around the origin, the normal is the point itself
+                assert!(
+                    normal.dot(point.normalize()) > 0.999,
+                    "normal {:?} at {:?} is not outward",
+                    normal,
+                    point
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,134 @@
+use cgmath::{InnerSpace, Vector3};
+use curve_ray::raytracing::{obb::Obb, ray::Ray};
+use curve_ray::utils::Rng;
+
+const EPSILON: f32 = 1e-4;
+
+/// Points of a long thin slab tilted around every axis
+fn tilted_points() -> Vec<Vector3<f32>> {
+    let axes = [
+        Vector3::new(2., 1., 0.5).normalize(),
+        Vector3::new(-1., 2., 0.).normalize(),
+    ];
+    let axes = [axes[0], axes[1], axes[0].cross(axes[1])];
+    let center = Vector3::new(1., -2., 0.5);
+    let mut rng = Rng::new(9);
+    (0..200)
+        .map(|_| {
+            center
+                + axes[0] * (rng.next_f32() - 0.5) * 6.
+                + axes[1] * (rng.next_f32() - 0.5) * 2.
+                + axes[2] * (rng.next_f32() - 0.5) * 0.5
+        })
+        .collect()
+}
+
+/// Unit cube turned by 45° around z, the corners of its section are on the axes at √2
+fn turned_cube() -> Obb {
+    let axis = Vector3::new(1., 1., 0.).normalize();
+    Obb {
+        center: Vector3::new(0., 0., 0.),
+        axes: [axis, Vector3::new(-axis.y, axis.x, 0.), Vector3::unit_z()],
+        half_size: Vector3::new(1., 1., 1.),
+    }
+}
+
+fn along_z(x: f32, y: f32) -> Ray {
+    Ray {
+        origin: Vector3::new(x, y, -5.),
+        direction: Vector3::unit_z(),
+    }
+}
+
+#[test]
+fn from_points_contains_points() {
+    let points = tilted_points();
+    let obb = Obb::from_points(&points);
+    for (index, axis) in obb.axes.iter().enumerate() {
+        assert!((axis.magnitude() - 1.).abs() < EPSILON);
+        for other in obb.axes[index + 1..].iter() {
+            assert!(axis.dot(*other).abs() < EPSILON);
+        }
+    }
+    for point in points.iter() {
+        for (index, axis) in obb.axes.iter().enumerate() {
+            let offset = axis.dot(point - obb.center).abs();
+            assert!(
+                offset <= obb.half_size[index] + EPSILON,
+                "{:?} outside along {:?}",
+                point,
+                axis
+            );
+        }
+    }
+    // the principal axes follow the slab, so the box is tighter than the axis aligned one
+    let volume = |size: Vector3<f32>| size.x * size.y * size.z;
+    let aabb = obb.to_aabb();
+    let aabb_size = Vector3::new(
+        aabb.max_x - aabb.min_x,
+        aabb.max_y - aabb.min_y,
+        aabb.max_z - aabb.min_z,
+    );
+    assert!(volume(obb.half_size * 2.) < volume(aabb_size) * 0.5);
+}
+
+#[test]
+fn to_aabb_encloses_corners() {
+    for obb in [Obb::from_points(&tilted_points()), turned_cube()] {
+        let aabb = obb.to_aabb();
+        for corner in obb.corners() {
+            assert!(aabb.min_x <= corner.x && corner.x <= aabb.max_x);
+            assert!(aabb.min_y <= corner.y && corner.y <= aabb.max_y);
+            assert!(aabb.min_z <= corner.z && corner.z <= aabb.max_z);
+        }
+    }
+    let aabb = turned_cube().to_aabb();
+    let half_diagonal = 2f32.sqrt();
+    assert!((aabb.max_x - half_diagonal).abs() < EPSILON);
+    assert!((aabb.min_y + half_diagonal).abs() < EPSILON);
+    assert!((aabb.max_z - 1.).abs() < EPSILON);
+}
+
+#[test]
+fn slab_test_follows_tilted_edges() {
+    let obb = turned_cube();
+
+    // through the center, parallel to two of the axes
+    let (t_n, t_f) = obb.get_slice_for_ray(&along_z(0., 0.)).unwrap();
+    assert!((t_n - 4.).abs() < EPSILON && (t_f - 6.).abs() < EPSILON);
+
+    // just inside and just outside of the tilted edge at x + y = √2,
+    // both inside of the axis aligned box
+    let inside = 0.68;
+    let outside = 0.75;
+    assert!(obb.get_slice_for_ray(&along_z(inside, inside)).is_some());
+    assert!(obb.get_slice_for_ray(&along_z(outside, outside)).is_none());
+    assert!(obb
+        .to_aabb()
+        .get_slice_for_ray(&along_z(outside, outside))
+        .is_some());
+
+    // oblique rays across the section past the corner on the x axis
+    let across = |y: f32| Ray {
+        origin: Vector3::new(3., y, 0.),
+        direction: Vector3::new(-1., 0., 0.),
+    };
+    let (t_n, t_f) = obb.get_slice_for_ray(&across(0.1)).unwrap();
+    let half_diagonal = 2f32.sqrt();
+    assert!((t_n - (3. - half_diagonal + 0.1)).abs() < EPSILON);
+    assert!((t_f - (3. + half_diagonal - 0.1)).abs() < EPSILON);
+    assert!(obb.get_slice_for_ray(&across(1.45)).is_none());
+
+    // from inside the slice starts at the origin, and boxes behind the ray are missed
+    let from_center = Ray {
+        origin: Vector3::new(0., 0., 0.),
+        direction: Vector3::new(0.3, -0.2, 1.),
+    };
+    let (t_n, _) = obb.get_slice_for_ray(&from_center).unwrap();
+    assert_eq!(t_n, 0.);
+    let away = Ray {
+        origin: Vector3::new(0., 0., 5.),
+        direction: Vector3::unit_z(),
+    };
+    assert!(obb.get_slice_for_ray(&away).is_none());
+}
@@ -0,0 +1,187 @@
+use std::io::ErrorKind;
+
+use cgmath::{InnerSpace, Vector2, Vector3};
+use curve_ray::obj::{read_obj, ObjOptions, ObjWriter};
+use curve_ray::raytracing::mesh::Mesh;
+use curve_ray::shapes::{get_box, get_curve_sphere};
+
+/// Square pyramid without the bottom, vertexes are used by the faces in order
+fn pyramid() -> Mesh {
+    Mesh {
+        vertexes: vec![
+            Vector3::new(0., 0., 1.),
+            Vector3::new(1., 0., 0.),
+            Vector3::new(0., 1., 0.),
+            Vector3::new(-1., 0., 0.),
+            Vector3::new(0., -1., 0.),
+        ],
+        indices: vec![[0, 1, 2], [0, 2, 3], [0, 3, 4], [0, 4, 1]],
+        normals: Some(vec![
+            Vector3::new(0., 0., 1.),
+            Vector3::new(1., 0., 0.),
+            Vector3::new(0., 1., 0.),
+            Vector3::new(-1., 0., 0.),
+            Vector3::new(0., -1., 0.),
+        ]),
+        uvs: Some(vec![
+            Vector2::new(0.5, 0.5),
+            Vector2::new(1., 0.5),
+            Vector2::new(0.5, 1.),
+            Vector2::new(0., 0.5),
+            Vector2::new(0.5, 0.125),
+        ]),
+        patch_barys: None,
+    }
+}
+
+fn round_trip(mesh: &Mesh) -> Mesh {
+    let mut writer = ObjWriter::new(Vec::new());
+    writer.write_mesh(mesh).unwrap();
+    read_obj(writer.into_inner().as_slice()).unwrap()
+}
+
+#[test]
+fn mesh_round_trip() {
+    let mesh = pyramid();
+    let read = round_trip(&mesh);
+    assert_eq!(read.vertexes, mesh.vertexes);
+    assert_eq!(read.indices, mesh.indices);
+    assert_eq!(read.normals, mesh.normals);
+    assert_eq!(read.uvs, mesh.uvs);
+
+    for (normals, uvs) in [(true, false), (false, true), (false, false)] {
+        let mut mesh = pyramid();
+        if !normals {
+            mesh.normals = None;
+        }
+        if !uvs {
+            mesh.uvs = None;
+        }
+        let read = round_trip(&mesh);
+        assert_eq!(read.vertexes, mesh.vertexes);
+        assert_eq!(read.indices, mesh.indices);
+        assert_eq!(read.normals, mesh.normals);
+        assert_eq!(read.uvs, mesh.uvs);
+    }
+}
+
+#[test]
+fn patch_normals_are_normals_of_the_surface() {
+    let mut patches = get_curve_sphere();
+    for patch in patches.iter_mut() {
+        patch.triangulate(4);
+    }
+    let mut writer = ObjWriter::new(Vec::new());
+    writer
+        .write_patches(&patches, &ObjOptions::default())
+        .unwrap();
+    let mesh = read_obj(writer.into_inner().as_slice()).unwrap();
+    assert!(mesh.indices.len() >= 8 * 16);
+    for (vertex, normal) in mesh.vertexes.iter().zip(mesh.normals.unwrap()) {
+        assert!(
+            normal.dot(vertex.normalize()) > 1. - 1e-6,
+            "{:?} at {:?}",
+            normal,
+            vertex
+        );
+    }
+}
+
+#[test]
+fn flat_patch_normals_are_normals_of_the_plane() {
+    let mut patches = get_box(Vector3::new(2., 1., 3.));
+    for patch in patches.iter_mut() {
+        patch.triangulate(3);
+    }
+    for patch in patches.iter() {
+        let mut writer = ObjWriter::new(Vec::new());
+        writer
+            .write_patches(std::slice::from_ref(patch), &ObjOptions::default())
+            .unwrap();
+        let mesh = read_obj(writer.into_inner().as_slice()).unwrap();
+        // walls of the box look outside
+        let normal = patch.base.normal();
+        assert!(normal.dot(patch.base.vertexes[0]) > 0.);
+        for exported in mesh.normals.unwrap() {
+            assert!(
+                (exported - normal).magnitude() < 1e-6,
+                "{:?} instead of {:?}",
+                exported,
+                normal
+            );
+        }
+    }
+}
+
+#[test]
+fn malformed_faces_are_errors() {
+    let header = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 0 0 1\nvt 0 0\nvn 0 0 1\n";
+    for (face, message) in [
+        ("f 1 2", "line 7: face needs at least 3 vertexes, got 2"),
+        ("f", "line 7: face needs at least 3 vertexes, got 0"),
+        ("f 1 2 x", "line 7: expected a vertex index, got `x`"),
+        ("f /1 2 3", "line 7: expected a vertex index, got ``"),
+        (
+            "f 1 2 9",
+            "line 7: vertex index 9 out of range of 4 defined",
+        ),
+        (
+            "f 0 1 2",
+            "line 7: vertex index 0 out of range of 4 defined",
+        ),
+        (
+            "f 1 2 -5",
+            "line 7: vertex index -5 out of range of 4 defined",
+        ),
+        (
+            "f 1/2 2/1 3/1",
+            "line 7: texture coord index 2 out of range of 1 defined",
+        ),
+        (
+            "f 1//1 2//1 3//a",
+            "line 7: expected a normal index, got `a`",
+        ),
+        (
+            "f 99999999999999999999 1 2",
+            "line 7: expected a vertex index",
+        ),
+    ] {
+        let error = read_obj(format!("{}{}\n", header, face).as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(error.to_string().contains(message), "`{}`: {}", face, error);
+    }
+    // negative indices count from the last vertex
+    let mesh = read_obj(format!("{}f -3 -2 -1\n", header).as_bytes()).unwrap();
+    assert_eq!(mesh.vertexes[0], Vector3::new(1., 0., 0.));
+}
+
+#[test]
+fn malformed_vertex_attributes_are_errors() {
+    let face = "f 1/1/1 2/1/1 3/1/1\n";
+    let vertexes = "v 0 0 0\nv 1 0 0\nv 0 1 0\n";
+    for (attributes, message) in [
+        ("vt 0.5 x\nvn 0 0 1\n", "line 4: expected a number, got `x`"),
+        ("vt\nvn 0 0 1\n", "line 4: expected a number"),
+        ("vt 0 0\nvn 0 0 0\n", "line 5: normal must have a direction"),
+        (
+            "vt 0 0\nvn 0 0 inf\n",
+            "line 5: normal must have a direction",
+        ),
+        ("vt 0 0\nvn 0 0\n", "line 5: expected a number"),
+    ] {
+        let text = format!("{}{}{}", vertexes, attributes, face);
+        let error = read_obj(text.as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(
+            error.to_string().contains(message),
+            "`{}`: {}",
+            attributes,
+            error
+        );
+    }
+    // v of texture coords is optional
+    let text = format!("{}vt 0.5\nvn 0 0 2\n{}", vertexes, face);
+    let mesh = read_obj(text.as_bytes()).unwrap();
+    assert_eq!(mesh.uvs.unwrap()[0], Vector2::new(0.5, 0.));
+    assert_eq!(mesh.normals.unwrap()[0], Vector3::new(0., 0., 1.));
+}
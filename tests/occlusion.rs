@@ -0,0 +1,76 @@
+use cgmath::{InnerSpace, Matrix3, SquareMatrix, Vector3};
+use curve_ray::raytracing::{ray::Ray, scene::Scene};
+use curve_ray::shapes::{get_box, get_curve_sphere, Shape, Transform};
+use curve_ray::utils::Rng;
+
+/// Rays from random points in front of the unit cube to random points inside of it,
+/// with random distance limits
+fn random_rays(count: usize) -> Vec<(Ray, f32)> {
+    let mut rng = Rng::new(7);
+    let mut random = |from: f32, to: f32| from + rng.next_f32() * (to - from);
+    (0..count)
+        .map(|_| {
+            let origin = Vector3::new(random(-2., 2.), random(-2., 2.), -3.);
+            let target = Vector3::new(random(-1., 1.), random(-1., 1.), random(-1., 1.));
+            let ray = Ray {
+                origin,
+                direction: (target - origin).normalize(),
+            };
+            (ray, random(0., 5.))
+        })
+        .collect()
+}
+
+#[test]
+fn occludes_agrees_with_intersect() {
+    let sphere = get_curve_sphere();
+    for (ray, t_max) in random_rays(5000) {
+        for part in sphere.iter() {
+            let hit = part.try_intersect(&ray).is_ok_and(|hit| hit.t < t_max);
+            assert_eq!(
+                part.occludes(&ray, t_max),
+                hit,
+                "ray from {:?} along {:?} to {}",
+                ray.origin,
+                ray.direction,
+                t_max
+            );
+        }
+    }
+}
+
+#[test]
+fn scene_occluded_agrees_with_nearest_hit() {
+    let mut shapes = vec![
+        Shape::new(
+            get_curve_sphere(),
+            Default::default(),
+            Transform::new(Vector3::new(0.3, 0., 0.2), Matrix3::identity(), 0.6),
+        ),
+        Shape::new(
+            get_box(Vector3::new(0.5, 0.5, 0.5)),
+            Default::default(),
+            Transform::from_rotation_y(Vector3::new(-0.5, -0.4, 0.), 30.),
+        ),
+    ];
+    for shape in shapes.iter_mut() {
+        for part in shape.patches.iter_mut() {
+            part.triangulate(2);
+        }
+    }
+    let scene = Scene::new(shapes, true);
+
+    for (ray, t_max) in random_rays(5000) {
+        let hit = scene
+            .nearest_curve_hit(&ray)
+            .is_some_and(|hit| hit.t < t_max);
+        assert_eq!(
+            scene.occluded(&ray, t_max),
+            hit,
+            "ray from {:?} along {:?} to {}",
+            ray.origin,
+            ray.direction,
+            t_max
+        );
+    }
+}
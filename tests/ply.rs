@@ -0,0 +1,128 @@
+use std::io::ErrorKind;
+
+use cgmath::{Vector2, Vector3};
+use curve_ray::ply::{read_ply, write_ply};
+use curve_ray::raytracing::mesh::Mesh;
+
+/// Square pyramid without the bottom
+fn pyramid() -> Mesh {
+    Mesh {
+        vertexes: vec![
+            Vector3::new(0., 0., 1.),
+            Vector3::new(1., 0., 0.),
+            Vector3::new(0., 1., 0.),
+            Vector3::new(-1., 0., 0.),
+            Vector3::new(0., -1., 0.),
+        ],
+        indices: vec![[0, 1, 2], [0, 2, 3], [0, 3, 4], [0, 4, 1]],
+        normals: Some(vec![
+            Vector3::new(0., 0., 1.),
+            Vector3::new(1., 0., 0.),
+            Vector3::new(0., 1., 0.),
+            Vector3::new(-1., 0., 0.),
+            Vector3::new(0., -1., 0.),
+        ]),
+        uvs: Some(vec![
+            Vector2::new(0.5, 0.5),
+            Vector2::new(1., 0.5),
+            Vector2::new(0.5, 1.),
+            Vector2::new(0., 0.5),
+            Vector2::new(0.5, 0.125),
+        ]),
+        patch_barys: None,
+    }
+}
+
+#[test]
+fn mesh_round_trip() {
+    for (normals, uvs) in [(true, true), (true, false), (false, true), (false, false)] {
+        let mut mesh = pyramid();
+        if !normals {
+            mesh.normals = None;
+        }
+        if !uvs {
+            mesh.uvs = None;
+        }
+        let mut bytes = Vec::new();
+        write_ply(&mut bytes, &mesh).unwrap();
+        let read = read_ply(bytes.as_slice()).unwrap();
+        assert_eq!(read.vertexes, mesh.vertexes);
+        assert_eq!(read.indices, mesh.indices);
+        assert_eq!(read.normals, mesh.normals);
+        assert_eq!(read.uvs, mesh.uvs);
+    }
+}
+
+#[test]
+fn bad_headers_are_errors() {
+    let body = "0 0 0\n1 0 0\n0 1 0\n3 0 1 2\n";
+    let vertex = "element vertex 3\nproperty float x\nproperty float y\nproperty float z\n";
+    let face = "element face 1\nproperty list uchar uint vertex_indices\n";
+    for (header, message) in [
+        ("plyx\n".to_string(), "line 1: expected `ply`"),
+        (
+            format!(
+                "ply\nformat binary_little_endian 1.0\n{}{}end_header\n",
+                vertex, face
+            ),
+            "line 2: only ascii PLY is supported, got binary_little_endian",
+        ),
+        (
+            format!("ply\n{}{}end_header\n", vertex, face),
+            "line 8: expected `format ascii 1.0` before `end_header`",
+        ),
+        (
+            format!(
+                "ply\nformat ascii 1.0\nelement vertex many\n{}end_header\n",
+                face
+            ),
+            "line 3: expected the count of element `vertex`, got `many`",
+        ),
+        (
+            format!(
+                "ply\nformat ascii 1.0\nelement vertex\n{}end_header\n",
+                face
+            ),
+            "line 3: expected `element <name> <count>`",
+        ),
+        (
+            "ply\nformat ascii 1.0\nproperty float x\n".to_string(),
+            "line 3: property `x` before any element",
+        ),
+        (
+            format!("ply\nformat ascii 1.0\n{}property float\n", vertex),
+            "line 7: expected `property <type> <name>`",
+        ),
+    ] {
+        let error = read_ply(format!("{}{}", header, body).as_bytes()).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+        assert!(
+            error.to_string().contains(message),
+            "{:?}: {}",
+            header,
+            error
+        );
+    }
+
+    // the header never ends
+    let error = read_ply(format!("ply\nformat ascii 1.0\n{}", vertex).as_bytes()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::UnexpectedEof);
+    assert!(error.to_string().contains("end of the header"));
+}
+
+#[test]
+fn zero_normals_are_errors() {
+    let header = "ply\nformat ascii 1.0\nelement vertex 3\nproperty float x\nproperty float y\n\
+                  property float z\nproperty float nx\nproperty float ny\nproperty float nz\n\
+                  element face 1\nproperty list uchar uint vertex_indices\nend_header\n";
+    let body = "0 0 0 0 0 1\n1 0 0 0 0 0\n0 1 0 0 0 1\n3 0 1 2\n";
+    let error = read_ply(format!("{}{}", header, body).as_bytes()).unwrap_err();
+    assert_eq!(error.kind(), ErrorKind::InvalidData);
+    assert!(
+        error
+            .to_string()
+            .contains("line 14: normal must have a direction"),
+        "{}",
+        error
+    );
+}
@@ -0,0 +1,97 @@
+use cgmath::{Matrix3, SquareMatrix, Vector3};
+use curve_ray::cpu_buffer::{CPUBuffer, Rect};
+use curve_ray::materials::WHITE;
+use curve_ray::raytracing::{
+    camera::Camera,
+    light::Light,
+    progressive::Accumulation,
+    renderer::{draw_rect, RendererKind},
+    scene::Scene,
+    tiles::TileScheduler,
+};
+use curve_ray::shapes::{get_box, get_curve_sphere, Shape, Transform};
+
+fn scene() -> Scene {
+    let mut shapes = vec![
+        Shape::new(
+            get_curve_sphere(),
+            Default::default(),
+            Transform::new(Vector3::new(0., 0., 0.), Matrix3::identity(), 0.5),
+        ),
+        Shape::new(
+            get_box(Vector3::new(3., 0.2, 3.)),
+            Default::default(),
+            Transform::new(Vector3::new(0., -0.6, 0.), Matrix3::identity(), 1.),
+        ),
+    ];
+    for shape in shapes.iter_mut() {
+        for part in shape.patches.iter_mut() {
+            part.triangulate(2);
+        }
+    }
+    let mut scene = Scene::new(shapes, true);
+    scene.lights.push(Light::Point {
+        position: Vector3::new(1., 2., -1.),
+        color: WHITE,
+        intensity: 4.,
+    });
+    scene
+}
+
+#[test]
+fn passes_add_up_to_samples_per_pixel() {
+    let scene = scene();
+    let camera = Camera {
+        origin: Vector3::new(0., 0.3, -2.),
+        direction: Vector3::new(0., 0., 1.),
+        fov: std::f32::consts::FRAC_PI_2,
+        ratio: 1.,
+    };
+    let (width, height) = (24, 24);
+    let scheduler = TileScheduler::new(8, 2);
+    let renderer = RendererKind::PathTraced.renderer(3);
+
+    let mut expected = CPUBuffer::new(width, height);
+    let rect = Rect {
+        left: 0,
+        bottom: 0,
+        width,
+        height,
+    };
+    draw_rect(
+        &mut expected,
+        &rect,
+        &camera,
+        &scene,
+        renderer.as_ref(),
+        &scheduler,
+        4,
+    );
+
+    let mut accumulation = Accumulation::new(width, height);
+    for _ in 0..4 {
+        accumulation.add_pass(&camera, &scene, std::slice::from_ref(&renderer), &scheduler);
+    }
+    assert_eq!(accumulation.passes, 4);
+    let mut image = CPUBuffer::new(width, height);
+    accumulation.resolve(&mut image);
+
+    let mut lit = 0;
+    for y in 0..height as usize {
+        for x in 0..width as usize {
+            for channel in 0..3 {
+                let (expected, actual) = (expected[(x, y)][channel], image[(x, y)][channel]);
+                assert!(
+                    (expected - actual).abs() <= 1e-5 * expected.abs().max(1.),
+                    "pixel {} {}: {} != {}",
+                    x,
+                    y,
+                    expected,
+                    actual
+                );
+            }
+            lit += (image[(x, y)][0] > 0.) as usize;
+        }
+    }
+    assert!(lit > 0, "the scene must be visible");
+}
@@ -0,0 +1,58 @@
+use cgmath::{InnerSpace, Vector3};
+use curve_ray::raytracing::whitted_raytracing::{refract, schlick};
+
+#[test]
+fn refraction_follows_snell_law() {
+    let normal = Vector3::new(0., 1., 0.);
+    for degrees in [0f32, 10., 30., 60., 85.] {
+        let angle = degrees.to_radians();
+        let direction = Vector3::new(angle.sin(), -angle.cos(), 0.);
+        let refracted = refract(direction, normal, 1.5).expect("entering glass always refracts");
+        assert!((refracted.magnitude() - 1.).abs() < 1e-5);
+        assert!(refracted.y < 0., "refracted ray must pass the surface");
+        // sin of the incident angle over sin of the refracted one
+        assert!(
+            (angle.sin() - 1.5 * refracted.x).abs() < 1e-5,
+            "{} degrees refracted to {:?}",
+            degrees,
+            refracted
+        );
+    }
+}
+
+#[test]
+fn total_internal_reflection_past_critical_angle() {
+    let normal = Vector3::new(0., 1., 0.);
+    let critical = (1f32 / 1.5).asin();
+    let ray = |angle: f32| Vector3::new(angle.sin(), -angle.cos(), 0.);
+    assert!(refract(ray(critical - 0.01), normal, 1. / 1.5).is_some());
+    assert!(refract(ray(critical + 0.01), normal, 1. / 1.5).is_none());
+}
+
+#[test]
+fn schlick_is_symmetric_and_grows_to_grazing() {
+    let normal = Vector3::new(0., 1., 0.);
+    let head_on = Vector3::new(0., -1., 0.);
+    let r0 = schlick(head_on, head_on, normal, 1.5);
+    assert!(
+        (r0 - 0.04).abs() < 1e-5,
+        "glass reflects 4% head on, got {}",
+        r0
+    );
+
+    // the same pair of directions from both sides of the surface
+    let outside = Vector3::new(0.5f32, -0.75f32.sqrt(), 0.);
+    let inside = refract(outside, normal, 1.5).unwrap();
+    let entering = schlick(outside, inside, normal, 1.5);
+    let leaving = schlick(-inside, -outside, -normal, 1. / 1.5);
+    assert!(
+        (entering - leaving).abs() < 1e-5,
+        "{} != {}",
+        entering,
+        leaving
+    );
+
+    let grazing = Vector3::new(0.99999f32, -(1f32 - 0.99999 * 0.99999).sqrt(), 0.);
+    let refracted = refract(grazing, normal, 1.5).unwrap();
+    assert!(schlick(grazing, refracted, normal, 1.5) > 0.9);
+}
@@ -0,0 +1,204 @@
+use cgmath::{InnerSpace, Vector3};
+use curve_ray::raytracing::{
+    curve_triangle::CurveTriangle,
+    hit::Intersect,
+    ray::Ray,
+    root_solver::{Convergence, RootIsolation, RootMethod, RootSolver},
+};
+use curve_ray::shapes::get_curve_sphere;
+use curve_ray::utils::Rng;
+
+const METHODS: [RootMethod; 3] = [
+    RootMethod::Bisection,
+    RootMethod::Secant,
+    RootMethod::Newton,
+];
+
+/// Octant of the unit sphere with x, y, z > 0
+fn octant() -> CurveTriangle {
+    get_curve_sphere().swap_remove(0)
+}
+
+/// Distances along the ray to the unit sphere around the origin
+fn sphere_roots(ray: &Ray) -> Option<(f32, f32)> {
+    let a = ray.direction.magnitude2();
+    let b = ray.origin.dot(ray.direction);
+    let c = ray.origin.magnitude2() - 1.;
+    let discriminant = b * b - a * c;
+    (discriminant > 0.).then(|| {
+        let root = discriminant.sqrt();
+        ((-b - root) / a, (-b + root) / a)
+    })
+}
+
+/// Rays entering and leaving the octant through its curve surface, along chords between
+/// two of its points. Directions are not unit, so `t` is not the distance.
+fn chords() -> Vec<Ray> {
+    let points = [
+        Vector3::new(1., 0.5, 0.5),
+        Vector3::new(0.5, 1., 0.5),
+        Vector3::new(0.5, 0.5, 1.),
+        Vector3::new(0.8, 0.3, 0.2),
+        Vector3::new(0.2, 0.4, 0.9),
+    ]
+    .map(|point: Vector3<f32>| point.normalize());
+    let mut rays = Vec::new();
+    for (index, &from) in points.iter().enumerate() {
+        for &to in points[index + 1..].iter() {
+            for scale in [0.5, 1., 3.] {
+                rays.push(Ray {
+                    origin: from - (to - from) * 0.5,
+                    direction: (to - from) * scale,
+                });
+            }
+        }
+    }
+    rays
+}
+
+#[test]
+fn intersect_all_finds_both_crossings_of_a_bulged_patch() {
+    let mut part = octant();
+    part.solver = RootSolver::default().with_isolation(RootIsolation::default());
+    for ray in chords() {
+        let (near, far) = sphere_roots(&ray).unwrap();
+        let hits = part.intersect_all(&ray);
+        let ts: Vec<f32> = hits.iter().map(|hit| hit.t).collect();
+        assert_eq!(
+            hits.len(),
+            2,
+            "ray from {:?} along {:?}: {:?}",
+            ray.origin,
+            ray.direction,
+            ts
+        );
+        for (hit, expected) in hits.iter().zip([near, far]) {
+            // the tolerance of the solver is on the function, the distance to the root point
+            let error = (hit.t - expected).abs() * ray.direction.magnitude();
+            assert!(error < 1e-4, "t {} instead of {}", hit.t, expected);
+        }
+        assert!(hits[0].front_face && !hits[1].front_face);
+    }
+}
+
+#[test]
+fn isolated_roots_agree_between_methods() {
+    for ray in chords() {
+        let roots: Vec<Vec<f32>> = METHODS
+            .iter()
+            .map(|&method| {
+                let mut part = octant();
+                part.solver = RootSolver::default()
+                    .with_method(method)
+                    .with_isolation(RootIsolation::default());
+                part.intersect_all(&ray).iter().map(|hit| hit.t).collect()
+            })
+            .collect();
+        for (method, ts) in METHODS.iter().zip(roots.iter()) {
+            assert_eq!(ts.len(), roots[0].len(), "{:?} found {:?}", method, ts);
+            for (t, bisection_t) in ts.iter().zip(roots[0].iter()) {
+                let difference = (t - bisection_t).abs() * ray.direction.magnitude();
+                assert!(difference < 1e-4, "{:?}: {} != {}", method, t, bisection_t);
+            }
+        }
+    }
+}
+
+/// Rays from points in front of the sphere to random points of the positive octant
+fn rays_at_octant(count: usize) -> Vec<Ray> {
+    let mut rng = Rng::new(11);
+    (0..count)
+        .map(|_| {
+            let origin = Vector3::new(rng.next_f32() * 4. - 1., rng.next_f32() * 4. - 1., 3.);
+            let target = Vector3::new(rng.next_f32(), rng.next_f32(), rng.next_f32()).normalize();
+            Ray {
+                origin,
+                direction: (target - origin).normalize(),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn secant_and_newton_converge_to_bisection_root() {
+    let solvers = METHODS.map(|method| {
+        let mut part = octant();
+        part.solver = RootSolver::default().with_method(method);
+        part
+    });
+    let tolerance = RootSolver::default().tolerance;
+    let mut hits = 0;
+    for ray in rays_at_octant(2000) {
+        let bisection = solvers[0].solve(&ray);
+        if let Ok(root) = bisection {
+            assert_eq!(root.convergence, Convergence::Converged);
+        }
+        for (method, part) in METHODS.iter().zip(solvers.iter()).skip(1) {
+            let root = part.solve(&ray);
+            assert_eq!(root.is_ok(), bisection.is_ok(), "{:?}", method);
+            if let (Ok(root), Ok(bisection)) = (root, bisection) {
+                assert_eq!(root.convergence, Convergence::Converged, "{:?}", method);
+                // refinement stops when the bracket or the function value is within the tolerance,
+                // the function is flat for grazing rays, so `t` may be further than the tolerance
+                let difference = (root.t - bisection.t).abs();
+                let step = part.intersect_step(root.t, &ray).abs();
+                assert!(
+                    difference <= 2. * tolerance || (step <= tolerance && difference < 1e-2),
+                    "{:?}: {} != {}, step {}",
+                    method,
+                    root.t,
+                    bisection.t,
+                    step
+                );
+            }
+        }
+        hits += bisection.is_ok() as usize;
+    }
+    assert!(hits > 100, "only {} rays hit the octant", hits);
+}
+
+#[test]
+fn missing_rays_have_no_hit_for_every_solver() {
+    let solvers = [
+        RootSolver::legacy(),
+        RootSolver::default().with_method(RootMethod::Bisection),
+        RootSolver::default().with_method(RootMethod::Secant),
+        RootSolver::default().with_method(RootMethod::Newton),
+        RootSolver::default().with_isolation(RootIsolation::default()),
+    ];
+    let misses = [
+        // away from the sphere
+        Ray {
+            origin: Vector3::new(0.5, 0.5, 2.),
+            direction: Vector3::new(0., 0., 1.),
+        },
+        // beside it
+        Ray {
+            origin: Vector3::new(1.2, 0.5, -2.),
+            direction: Vector3::new(0., 0., 1.),
+        },
+        // through the shell above the base, past the curved surface
+        Ray {
+            origin: Vector3::new(1.05, 1.05, -2.),
+            direction: Vector3::new(0., 0., 1.),
+        },
+        // through another octant
+        Ray {
+            origin: Vector3::new(-0.5, -0.5, -2.),
+            direction: Vector3::new(0., 0., 1.),
+        },
+    ];
+    for solver in solvers {
+        let mut part = octant();
+        part.solver = solver;
+        for ray in misses.iter() {
+            assert!(
+                part.intersect(ray).is_none(),
+                "{:?} hit the ray from {:?}",
+                solver,
+                ray.origin
+            );
+            assert!(part.intersect_all(ray).is_empty());
+        }
+    }
+}
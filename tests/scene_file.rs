@@ -0,0 +1,159 @@
+use std::io::ErrorKind;
+use std::path::Path;
+
+use cgmath::{InnerSpace, Vector3};
+use curve_ray::raytracing::ray::Ray;
+use curve_ray::scene_file::{load_scene_file, read_scene_file};
+
+const SCENE: &str = r#"{
+    "camera": { "origin": [0, 0, -3], "look_at": [0, 0, 0] },
+    "materials": { "red": { "color": [0.9, 0.2, 0.2] } },
+    "lights": [{ "type": "point", "position": [2, 3, -2] }],
+    "objects": [
+        { "type": "curve_sphere", "radius": 0.8, "material": "red" },
+        {
+            "type": "curve_triangle",
+            "vertexes": [[1, 0, 0], [0, 1, 0], [0, 0, 1]],
+            "pivots": [[0, 0, 0], [0, 0, 0], [0, 0, 0]]
+        }
+    ]
+}"#;
+
+/// Error of the scene with `from` replaced by `to`
+fn error(from: &str, to: &str) -> String {
+    assert!(SCENE.contains(from));
+    let text = SCENE.replacen(from, to, 1);
+    match read_scene_file(&text, Path::new("")) {
+        Ok(_) => panic!("`{}` is accepted", to),
+        Err(error) => {
+            assert_eq!(error.kind(), ErrorKind::InvalidData);
+            error.to_string()
+        }
+    }
+}
+
+fn assert_contains(message: &str, parts: &[&str]) {
+    for part in parts {
+        assert!(message.contains(part), "`{}` not in {}", part, message);
+    }
+}
+
+#[test]
+fn scene_is_read() {
+    let scene_file = read_scene_file(SCENE, Path::new("")).unwrap();
+    assert_eq!(scene_file.scene.shapes.len(), 2);
+}
+
+#[test]
+fn unknown_fields_point_to_the_field() {
+    assert_contains(
+        &error(
+            r#""look_at": [0, 0, 0]"#,
+            r#""look_at": [0, 0, 0], "zoom": 2"#,
+        ),
+        &["camera.zoom: unknown field `zoom`", "at line 2 column 66"],
+    );
+    // tagged objects and lights are read as a whole, errors point to them
+    assert_contains(
+        &error(r#""radius": 0.8"#, r#""radius": 0.8, "colour": [1, 1, 1]"#),
+        &["objects[0]: unknown field `colour`", "at line"],
+    );
+    assert_contains(
+        &error(
+            r#""position": [2, 3, -2]"#,
+            r#""position": [2, 3, -2], "range": 4"#,
+        ),
+        &["lights[0]: unknown field `range`", "at line"],
+    );
+}
+
+#[test]
+fn wrong_types_point_to_the_field() {
+    assert_contains(
+        &error("[0.9, 0.2, 0.2]", "[0.9, 0.2]"),
+        &[
+            "materials.red.color: invalid length 2",
+            "at line 3 column 47",
+        ],
+    );
+    assert_contains(
+        &error(r#""origin": [0, 0, -3]"#, r#""origin": "front""#),
+        &[
+            r#"camera.origin: invalid type: string "front", expected an array of length 3"#,
+            "at line 2 column 33",
+        ],
+    );
+    assert_contains(
+        &error(r#""radius": 0.8"#, r#""radius": "big""#),
+        &[
+            r#"objects[0]: invalid type: string "big", expected f32"#,
+            "at line",
+        ],
+    );
+    assert_contains(
+        &error(
+            r#""pivots": [[0, 0, 0], [0, 0, 0],"#,
+            r#""pivots": [[0, 0, 0], [0, true, 0],"#,
+        ),
+        &["objects[1]: invalid type: boolean `true`", "at line"],
+    );
+    assert_contains(
+        &error(r#""type": "curve_sphere""#, r#""type": "cube""#),
+        &["objects[0].type: unknown variant `cube`", "at line 6 column 24"],
+    );
+    assert_contains(
+        &error(
+            r#""radius": 0.8"#,
+            r#""radius": 0.8, "transform": { "scale": 0 }"#,
+        ),
+        &["objects[0].transform: scale must be positive"],
+    );
+}
+
+#[test]
+fn objects_are_transformed() {
+    let text = SCENE.replacen(
+        r#""radius": 0.8"#,
+        r#""center": [1, 0, 0], "radius": 0.5,
+            "transform": { "translation": [0, 2, 0], "rotation": [0, 0, 90], "scale": 2 }"#,
+        1,
+    );
+    let scene_file = read_scene_file(&text, Path::new("")).unwrap();
+    // the center goes to 2 * (0, 1, 0) + (0, 2, 0), the radius to 1
+    let transform = scene_file.scene.shapes[0].transform;
+    assert!(
+        (transform.point(Vector3::new(0., 0., 0.)) - Vector3::new(0., 4., 0.)).magnitude() < 1e-5
+    );
+    assert!((transform.scale - 1.).abs() < 1e-6);
+
+    let ray = Ray {
+        origin: Vector3::new(0., 4., -5.),
+        direction: Vector3::new(0., 0., 1.),
+    };
+    let hit = scene_file.scene.nearest_curve_hit(&ray).unwrap();
+    assert!((hit.t - 4.).abs() < 1e-2, "t {}", hit.t);
+    assert!((hit.outward_normal() - Vector3::new(0., 0., -1.)).magnitude() < 1e-2);
+}
+
+#[test]
+fn missing_file_names_the_file() {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("scenes/missing.json");
+    let error = load_scene_file(&path).err().unwrap();
+    assert_eq!(error.kind(), ErrorKind::NotFound);
+    assert!(error
+        .to_string()
+        .starts_with(&format!("{}: ", path.display())));
+
+    // and errors of existing files too
+    let path = std::env::temp_dir().join(format!("curve_ray_scene_{}.json", std::process::id()));
+    std::fs::write(&path, SCENE.replacen("0.8", "\"big\"", 1)).unwrap();
+    let error = load_scene_file(&path).err().unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_contains(
+        &error.to_string(),
+        &[
+            &format!("{}: objects[0]: invalid type", path.display()),
+            "at line",
+        ],
+    );
+}
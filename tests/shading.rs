@@ -0,0 +1,168 @@
+use cgmath::{InnerSpace, Vector3};
+use curve_ray::materials::{Material, RED, WHITE};
+use curve_ray::raytracing::{
+    hit::Hit,
+    light::Light,
+    ray::Ray,
+    scene::Scene,
+    shading::{direct_lighting, reflected_light},
+};
+use curve_ray::shapes::{get_cornell_box, get_wall, Shape, Transform};
+use curve_ray::utils::Rng;
+
+const EPSILON: f32 = 1e-5;
+
+fn point_light(position: Vector3<f32>) -> Light {
+    Light::Point {
+        position,
+        color: WHITE,
+        intensity: 2.,
+    }
+}
+
+fn triangulated(mut shapes: Vec<Shape>) -> Vec<Shape> {
+    for shape in shapes.iter_mut() {
+        for patch in shape.patches.iter_mut() {
+            patch.triangulate(3);
+        }
+    }
+    shapes
+}
+
+/// Floor at y = 0 looking up, and a blocker at y = 2 over the half x > 0
+fn floor_scene(lights: Vec<Light>) -> Scene {
+    let corner = |x: f32, y: f32, z: f32| Vector3::new(x, y, z);
+    let floor = get_wall([
+        corner(-4., 0., -4.),
+        corner(-4., 0., 4.),
+        corner(4., 0., 4.),
+        corner(4., 0., -4.),
+    ]);
+    let blocker = get_wall([
+        corner(0.5, 2., -1.),
+        corner(0.5, 2., 1.),
+        corner(3., 2., 1.),
+        corner(3., 2., -1.),
+    ]);
+    let shapes = vec![
+        Shape::new(floor, Material::default(), Transform::identity()),
+        Shape::new(blocker, Material::default(), Transform::identity()),
+    ];
+    let mut scene = Scene::new(triangulated(shapes), false);
+    scene.lights = lights;
+    scene
+}
+
+/// Hit of the floor from straight above the point
+fn floor_hit(scene: &Scene, x: f32, z: f32) -> (Ray, Hit) {
+    let ray = Ray {
+        origin: Vector3::new(x, 1., z),
+        direction: Vector3::new(0., -1., 0.),
+    };
+    let hit = scene.nearest_curve_hit(&ray).expect("floor hit");
+    (ray, hit)
+}
+
+fn assert_close(actual: [f32; 3], expected: [f32; 3], tolerance: f32) {
+    for (a, e) in actual.iter().zip(expected) {
+        assert!(
+            (a - e).abs() < tolerance,
+            "{:?} instead of {:?}",
+            actual,
+            expected
+        );
+    }
+}
+
+#[test]
+fn point_light_falls_off_with_squared_distance() {
+    let material = Material::default();
+    let mut rng = Rng::new(1);
+    let mut lit = Vec::new();
+    for height in [0.5, 1., 1.5] {
+        let scene = floor_scene(vec![point_light(Vector3::new(-1., height, 0.))]);
+        let (ray, hit) = floor_hit(&scene, -1., 0.);
+        let direct = direct_lighting(&scene, &ray, &hit, &material, &mut rng);
+        let reflected = reflected_light(&scene, &scene.lights, &ray, &hit, &material, 1, &mut rng);
+        // light straight above, Lambert cosine is one
+        assert_close(reflected, [2. / (height * height); 3], 1e-3);
+        lit.push(direct);
+    }
+    // the ambient part is the same at every height
+    let expected = 2. / 0.25 - 2. / 2.25;
+    assert_close(
+        lit[0].map(|channel| channel - lit[2][0]),
+        [expected; 3],
+        1e-3,
+    );
+}
+
+#[test]
+fn point_light_falls_off_with_the_cosine() {
+    let material = Material::default();
+    let mut rng = Rng::new(1);
+    let light = Vector3::new(-1., 1., 0.);
+    let scene = floor_scene(vec![point_light(light)]);
+    for x in [-1.5, -2., -3.] {
+        let (ray, hit) = floor_hit(&scene, x, 0.5);
+        let to_light = light - hit.point;
+        let distance = to_light.magnitude();
+        let cosine = to_light.y / distance;
+        let reflected = reflected_light(&scene, &scene.lights, &ray, &hit, &material, 1, &mut rng);
+        assert_close(reflected, [2. * cosine / (distance * distance); 3], 1e-4);
+    }
+}
+
+#[test]
+fn shadowed_points_get_only_ambient_light() {
+    let material = Material::default();
+    let mut rng = Rng::new(1);
+    let scene = floor_scene(vec![point_light(Vector3::new(1.5, 4., 0.))]);
+    let unlit = floor_scene(vec![point_light(Vector3::new(1.5, -4., 0.))]);
+
+    let (ray, hit) = floor_hit(&scene, 1.5, 0.);
+    let reflected = reflected_light(&scene, &scene.lights, &ray, &hit, &material, 1, &mut rng);
+    assert_eq!(reflected, [0.; 3]);
+    // the same as a light under the floor
+    let (unlit_ray, unlit_hit) = floor_hit(&unlit, 1.5, 0.);
+    assert_eq!(
+        direct_lighting(&scene, &ray, &hit, &material, &mut rng),
+        direct_lighting(&unlit, &unlit_ray, &unlit_hit, &material, &mut rng),
+    );
+
+    // out of the shadow of the blocker
+    let (ray, hit) = floor_hit(&scene, -1.5, 0.);
+    let reflected = reflected_light(&scene, &scene.lights, &ray, &hit, &material, 1, &mut rng);
+    assert!(reflected.iter().all(|&channel| channel > EPSILON));
+}
+
+#[test]
+fn cornell_red_wall_is_lit_by_its_plane_normal() {
+    let material = Material {
+        color: RED,
+        ..Material::default()
+    };
+    let mut rng = Rng::new(1);
+    let light = Vector3::new(0., 0.5, 0.);
+    let mut scene = Scene::new(triangulated(get_cornell_box()), false);
+    scene.lights = vec![point_light(light)];
+    for y in [0.3, 0.6, 0.9] {
+        for z in [-0.8, -0.2, 0.4, 0.9] {
+            let ray = Ray {
+                origin: Vector3::new(0., y, z),
+                direction: Vector3::new(-1., 0., 0.),
+            };
+            let hit = scene.nearest_curve_hit(&ray).expect("red wall hit");
+            let to_light = light - hit.point;
+            let distance = to_light.magnitude();
+            let lambert = to_light.x / distance;
+            let reflected =
+                reflected_light(&scene, &scene.lights, &ray, &hit, &material, 1, &mut rng);
+            assert_close(
+                reflected,
+                [2. * lambert / (distance * distance), 0., 0.],
+                1e-4,
+            );
+        }
+    }
+}
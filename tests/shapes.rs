@@ -0,0 +1,157 @@
+use cgmath::{Deg, InnerSpace, Matrix3, Vector3};
+use curve_ray::materials::{GREEN, RED, WHITE};
+use curve_ray::raytracing::{
+    hit::{Hit, Intersect},
+    ray::Ray,
+    scene::Scene,
+};
+use curve_ray::shapes::{get_cornell_box, get_curve_sphere, Transform};
+
+const EPSILON: f32 = 1e-5;
+
+fn assert_close(actual: Vector3<f32>, expected: Vector3<f32>, tolerance: f32) {
+    assert!(
+        (actual - expected).magnitude() < tolerance,
+        "{:?} instead of {:?}",
+        actual,
+        expected
+    );
+}
+
+fn transform() -> Transform {
+    let rotation = Matrix3::from_angle_z(Deg(30.)) * Matrix3::from_angle_x(Deg(-50.));
+    Transform::new(Vector3::new(0.5, -2., 3.), rotation, 2.5)
+}
+
+fn cornell_scene(with_bvh: bool) -> Scene {
+    let mut shapes = get_cornell_box();
+    for shape in shapes.iter_mut() {
+        for patch in shape.patches.iter_mut() {
+            patch.triangulate(3);
+        }
+    }
+    Scene::new(shapes, with_bvh)
+}
+
+fn nearest_hit(patches: &[impl Intersect], ray: &Ray) -> Option<Hit> {
+    patches
+        .iter()
+        .filter_map(|patch| patch.intersect(ray))
+        .min_by(|a, b| a.t.total_cmp(&b.t))
+}
+
+#[test]
+fn local_rays_go_through_local_points() {
+    let transform = transform();
+    let local_point = Vector3::new(0.2, -0.7, 0.4);
+    let world_point = transform.point(local_point);
+    let ray = Ray {
+        origin: Vector3::new(-4., 1., 2.),
+        direction: (world_point - Vector3::new(-4., 1., 2.)).normalize(),
+    };
+    let distance = (world_point - ray.origin).magnitude();
+
+    let local = transform.ray_to_local(&ray);
+    assert!((local.direction.magnitude() - 1.).abs() < EPSILON);
+    // distances along the local ray are `1 / scale` of the world ones
+    assert_close(
+        local.get_point(distance / transform.scale),
+        local_point,
+        EPSILON * 10.,
+    );
+    assert_close(transform.direction(local.direction), ray.direction, EPSILON);
+}
+
+#[test]
+fn local_hits_go_back_to_the_world() {
+    let transform = transform();
+    let mut sphere = get_curve_sphere();
+    for patch in sphere.iter_mut() {
+        patch.triangulate(3);
+    }
+    let center = transform.point(Vector3::new(0., 0., 0.));
+    for direction in [
+        Vector3::new(0., 0., 1.),
+        Vector3::new(1., -1., 0.5),
+        Vector3::new(-0.3, 1., -0.2),
+    ] {
+        let direction = direction.normalize();
+        let ray = Ray {
+            origin: center - direction * 10.,
+            direction,
+        };
+        let local_ray = transform.ray_to_local(&ray);
+        let local_hit = nearest_hit(&sphere, &local_ray).expect("local hit");
+        let hit = transform.hit_to_world(local_hit, &ray);
+
+        assert_close(hit.point, ray.origin + ray.direction * hit.t, EPSILON * 10.);
+        assert_close(hit.point, transform.point(local_hit.point), 1e-4);
+        assert_close(hit.normal, transform.direction(local_hit.normal), EPSILON);
+        assert!((hit.normal.magnitude() - 1.).abs() < EPSILON);
+        assert!(hit.normal.dot(ray.direction) < 0.);
+        // the unit sphere is scaled to the radius of the scale
+        assert!(
+            ((hit.point - center).magnitude() - transform.scale).abs() < 0.05 * transform.scale,
+            "{:?} is off the sphere",
+            hit.point
+        );
+    }
+}
+
+#[test]
+fn combined_transforms_apply_one_after_another() {
+    let inner = transform();
+    let outer = Transform::new(
+        Vector3::new(-1., 0., 2.),
+        Matrix3::from_angle_y(Deg(75.)),
+        0.5,
+    );
+    let combined = inner.then(&outer);
+    let point = Vector3::new(0.3, 1.2, -0.8);
+    assert_close(
+        combined.point(point),
+        outer.point(inner.point(point)),
+        EPSILON,
+    );
+}
+
+#[test]
+fn cornell_walls_have_their_colors_and_look_inside() {
+    for with_bvh in [false, true] {
+        let scene = cornell_scene(with_bvh);
+        // above the boxes and the sphere
+        let origin = Vector3::new(0., 0.5, 0.);
+        let walls = [
+            (Vector3::new(-1., 0., 0.), RED),
+            (Vector3::new(1., 0., 0.), GREEN),
+            (Vector3::new(0., 0., 1.), WHITE),
+            (Vector3::new(0., 1., 0.), WHITE),
+        ];
+        for (direction, color) in walls {
+            for offset in [-0.4, 0., 0.3] {
+                let side = if direction.y == 0. {
+                    Vector3::new(direction.z, offset, direction.x) * 0.5
+                } else {
+                    Vector3::new(offset, 0., offset) * 0.5
+                };
+                let ray = Ray {
+                    origin: origin + side,
+                    direction,
+                };
+                let hit = scene.nearest_curve_hit(&ray).expect("wall hit");
+                let expected_t = if direction.y == 0. { 1. } else { 0.5 };
+                assert!(
+                    (hit.t - expected_t).abs() < EPSILON,
+                    "{} instead of {} along {:?}",
+                    hit.t,
+                    expected_t,
+                    direction
+                );
+                assert_eq!(scene.hit_material(&hit).color, color);
+                // walls are flat, their normal is exact anywhere on them
+                assert_close(hit.normal, -direction, 1e-6);
+                assert!(hit.front_face, "wall along {:?} looks outside", direction);
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+
+use cgmath::Vector3;
+use curve_ray::accuracy::{sphere_report, AccuracyOptions};
+use curve_ray::raytracing::{fit::Sphere, mesh::Mesh};
+use curve_ray::shapes::get_curve_sphere;
+
+/// Faces of every directed edge by its vertex indices
+fn directed_edges(mesh: &Mesh) -> HashMap<(u32, u32), usize> {
+    let mut edges = HashMap::new();
+    for face in mesh.indices.iter() {
+        for edge in 0..3 {
+            *edges.entry((face[edge], face[(edge + 1) % 3])).or_default() += 1;
+        }
+    }
+    edges
+}
+
+#[test]
+fn welded_sphere_tessellation_is_closed() {
+    let patches = get_curve_sphere();
+    for (tolerance, max_depth) in [(1e-2, 4), (1e-3, 6), (1e-4, 7)] {
+        let mesh = Mesh::tessellate(&patches, tolerance, max_depth);
+        let edges = directed_edges(&mesh);
+        for (&(a, b), &faces) in edges.iter() {
+            // faces are wound outward, so the neighbour goes along the edge the other way
+            assert_eq!(
+                faces, 1,
+                "edge {} {} repeats at tolerance {}",
+                a, b, tolerance
+            );
+            assert_eq!(
+                edges.get(&(b, a)),
+                Some(&1),
+                "edge {:?} {:?} has a single face at tolerance {}",
+                mesh.vertexes[a as usize],
+                mesh.vertexes[b as usize],
+                tolerance
+            );
+        }
+        // a closed triangle mesh of genus 0
+        let euler = mesh.vertexes.len() as i64 - edges.len() as i64 / 2 + mesh.indices.len() as i64;
+        assert_eq!(euler, 2, "at tolerance {}", tolerance);
+    }
+}
+
+#[test]
+fn welded_hemisphere_tessellation_is_open_only_on_the_border() {
+    // octants with z > 0, the equator is the border
+    let patches: Vec<_> = get_curve_sphere()
+        .into_iter()
+        .filter(|patch| patch.base.vertexes.iter().any(|vertex| vertex.z > 0.5))
+        .collect();
+    assert_eq!(patches.len(), 4);
+    let mesh = Mesh::tessellate(&patches, 1e-3, 6);
+    let edges = directed_edges(&mesh);
+    let mut border = 0;
+    for (&(a, b), &faces) in edges.iter() {
+        assert_eq!(faces, 1);
+        if edges.contains_key(&(b, a)) {
+            continue;
+        }
+        border += 1;
+        for vertex in [a, b] {
+            let vertex = mesh.vertexes[vertex as usize];
+            assert!(
+                vertex.z.abs() < 1e-6,
+                "open edge at {:?} inside the hemisphere",
+                vertex
+            );
+        }
+    }
+    assert!(border >= 4, "only {} edges on the equator", border);
+}
+
+#[test]
+fn accuracy_report_has_no_open_edges() {
+    let sphere = Sphere {
+        center: Vector3::new(0., 0., 0.),
+        radius: 1.,
+    };
+    let options = AccuracyOptions {
+        surface_samples: 4,
+        ray_resolution: 4,
+        ..Default::default()
+    };
+    let report = sphere_report(&mut get_curve_sphere(), &sphere, &options);
+    assert_eq!(report.tessellation.open_edges, 0);
+    assert_eq!(report.triangulation.open_edges, 0);
+    assert!(report.tessellation.triangles > 8);
+    // outward winding
+    assert!(report.tessellation.normal_deviation.max < 90.);
+}
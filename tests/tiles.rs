@@ -0,0 +1,80 @@
+use cgmath::{Matrix3, SquareMatrix, Vector3};
+use curve_ray::cpu_buffer::{CPUBuffer, Rect};
+use curve_ray::raytracing::{
+    camera::Camera,
+    renderer::{draw_rect, RendererKind},
+    scene::Scene,
+    tiles::TileScheduler,
+};
+use curve_ray::shapes::{get_curve_sphere, Shape, Transform};
+
+fn render(scheduler: &TileScheduler, kind: RendererKind, width: u32, height: u32) -> CPUBuffer {
+    let mut shape = Shape::new(
+        get_curve_sphere(),
+        Default::default(),
+        Transform::new(Vector3::new(0., 0., 0.), Matrix3::identity(), 1.),
+    );
+    for part in shape.patches.iter_mut() {
+        part.triangulate(3);
+    }
+    let scene = Scene::new(vec![shape], true);
+    let camera = Camera {
+        origin: Vector3::new(0.3, 0.2, -3.),
+        direction: Vector3::new(0., 0., 1.),
+        fov: std::f32::consts::FRAC_PI_2,
+        ratio: width as f32 / height as f32,
+    };
+    let rect = Rect {
+        left: 0,
+        bottom: 0,
+        width,
+        height,
+    };
+    let mut cpu_buffer = CPUBuffer::new(width, height);
+    draw_rect(
+        &mut cpu_buffer,
+        &rect,
+        &camera,
+        &scene,
+        kind.renderer(2).as_ref(),
+        scheduler,
+        2,
+    );
+    cpu_buffer
+}
+
+#[test]
+fn threaded_render_is_identical_to_serial() {
+    // 7 divides neither side, so the right and top tiles are partial
+    let (width, height) = (45, 31);
+    for kind in [
+        RendererKind::Triangulated,
+        RendererKind::CurveSurface,
+        RendererKind::Shaded,
+    ] {
+        let serial = render(&TileScheduler::serial(), kind, width, height);
+        let threaded = render(&TileScheduler::new(7, 4), kind, width, height);
+
+        let mut lit = 0;
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                let (expected, actual) = (serial[(x, y)], threaded[(x, y)]);
+                assert_eq!(
+                    expected.map(f32::to_bits),
+                    actual.map(f32::to_bits),
+                    "{} pixel {} {}",
+                    kind,
+                    x,
+                    y
+                );
+                lit += (expected != [0.; 3]) as usize;
+            }
+        }
+        assert!(lit > 0, "{} rendered nothing", kind);
+
+        let (mut serial_bytes, mut threaded_bytes) = (Vec::new(), Vec::new());
+        serial.write_ppm(&mut serial_bytes).unwrap();
+        threaded.write_ppm(&mut threaded_bytes).unwrap();
+        assert_eq!(serial_bytes, threaded_bytes);
+    }
+}